@@ -1,13 +1,99 @@
-use crate::game::GameRules;
+use std::time::Duration;
+
+use crate::background::{SearchControl, SearchProgress};
+use crate::game::{GameRules, PlayerIndex};
 
 pub trait GameAi<Rules: GameRules> {
     fn determine_next_move(&mut self, gamestate: &Rules::State) -> Rules::Action;
     fn name(&self) -> String;
+
+    /// Like `determine_next_move`, but reports the progress of the search to `control`, and
+    /// returns the best move found so far once it is stopped. By default, the search always runs
+    /// to its end and only the chosen move is reported.
+    fn determine_next_move_with_control(
+        &mut self,
+        gamestate: &Rules::State,
+        control: &SearchControl<Rules::Action>,
+    ) -> Rules::Action {
+        let action = self.determine_next_move(gamestate);
+        control.report(SearchProgress {
+            best_move: Some(action.clone()),
+            ..Default::default()
+        });
+        action
+    }
+
+    /// Keeps searching on a background thread while the opponent thinks about their move in
+    /// `gamestate`. The next `determine_next_move` stops the background search and reuses its
+    /// work for the state it is given. Does nothing by default.
+    fn start_pondering(&mut self, _gamestate: &Rules::State) {}
+
+    /// Stops the background search of `start_pondering`, keeping its results
+    fn stop_pondering(&mut self) {}
+}
+
+/// Boxed AIs can be swapped at runtime, for example in a `BackgroundAi`
+impl<Rules: GameRules, Ai: GameAi<Rules> + ?Sized> GameAi<Rules> for Box<Ai> {
+    fn determine_next_move(&mut self, gamestate: &Rules::State) -> Rules::Action {
+        (**self).determine_next_move(gamestate)
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn determine_next_move_with_control(
+        &mut self,
+        gamestate: &Rules::State,
+        control: &SearchControl<Rules::Action>,
+    ) -> Rules::Action {
+        (**self).determine_next_move_with_control(gamestate, control)
+    }
+
+    fn start_pondering(&mut self, gamestate: &Rules::State) {
+        (**self).start_pondering(gamestate)
+    }
+
+    fn stop_pondering(&mut self) {
+        (**self).stop_pondering()
+    }
 }
 
 pub trait Evaluator {
     type Rules: GameRules;
 
-    /// Player Zero is maximizing, player one is minimizing value
-    fn value(&self, state: &<Self::Rules as GameRules>::State) -> f32;
+    /// Estimated value of the state for `player`, higher is better.
+    /// Two player searches assume a zero sum game, where the value for one player is the
+    /// negative value for the other.
+    fn value(&self, state: &<Self::Rules as GameRules>::State, player: &PlayerIndex) -> f32;
+
+    /// Whether the action changes the value so much, that its consequences should be searched
+    /// before evaluating the state. Searches can extend beyond their depth along noisy actions.
+    fn is_noisy(
+        &self,
+        _state: &<Self::Rules as GameRules>::State,
+        _action: &<Self::Rules as GameRules>::Action,
+    ) -> bool {
+        false
+    }
+}
+
+/// Game specific estimate of how promising an action is for the player to move.
+/// Search algorithms use it to prioritize actions.
+pub trait ActionHeuristic {
+    type Rules: GameRules;
+
+    /// Higher scores are more promising
+    fn score(
+        &self,
+        state: &<Self::Rules as GameRules>::State,
+        action: &<Self::Rules as GameRules>::Action,
+    ) -> f32;
+}
+
+/// Budget for anytime search algorithms
+#[derive(Clone, Debug)]
+pub enum StopCondition {
+    Iterations(usize),
+    Time(Duration),
 }
@@ -0,0 +1,229 @@
+//! Non-blocking searches, which GUIs and protocol servers can poll, cancel or cut short
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::ai::GameAi;
+use crate::game::GameRules;
+use crate::ponder::Pondering;
+
+/// Intermediate result of a running search
+#[derive(Clone, Debug)]
+pub struct SearchProgress<Action> {
+    /// Move the search would play if it was stopped now
+    pub best_move: Option<Action>,
+    /// Depth of the last completed iteration, or of the search tree
+    pub depth: usize,
+    /// Searched positions, or nodes of the search tree
+    pub nodes: u64,
+    /// Playouts of Monte Carlo searches
+    pub playouts: u64,
+    /// Value of the best move for the player to move: The evaluation of searches with an
+    /// evaluator, or the mean reward of Monte Carlo searches
+    pub value: Option<f32>,
+    /// Moves both players are expected to play, starting with the best move
+    pub principal_variation: Vec<Action>,
+}
+
+impl<Action> Default for SearchProgress<Action> {
+    fn default() -> Self {
+        SearchProgress {
+            best_move: None,
+            depth: 0,
+            nodes: 0,
+            playouts: 0,
+            value: None,
+            principal_variation: Vec::new(),
+        }
+    }
+}
+
+/// Handle shared between a search and its caller: The caller can stop the search, and the search
+/// reports its progress. Clones refer to the same search.
+pub struct SearchControl<Action> {
+    stop: Arc<AtomicBool>,
+    /// None if reports are discarded
+    progress: Option<Arc<Mutex<SearchProgress<Action>>>>,
+}
+
+impl<Action> SearchControl<Action> {
+    pub fn new() -> SearchControl<Action> {
+        SearchControl {
+            stop: Arc::new(AtomicBool::new(false)),
+            progress: Some(Arc::new(Mutex::new(SearchProgress::default()))),
+        }
+    }
+
+    /// Shares the stop flag, but discards reports, for helper searches whose progress does not
+    /// represent the whole search
+    pub fn silent(&self) -> SearchControl<Action> {
+        SearchControl {
+            stop: self.stop.clone(),
+            progress: None,
+        }
+    }
+
+    /// Asks the search to return its best move as soon as possible
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Flag behind `stop`, for searches which check it in their inner loops
+    pub fn stop_flag(&self) -> &AtomicBool {
+        &self.stop
+    }
+
+    pub fn report(&self, progress: SearchProgress<Action>) {
+        if let Some(shared) = &self.progress {
+            *shared.lock().unwrap() = progress;
+        }
+    }
+}
+
+impl<Action: Clone> SearchControl<Action> {
+    /// Last reported progress
+    pub fn progress(&self) -> SearchProgress<Action> {
+        self.progress
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+}
+
+impl<Action> Default for SearchControl<Action> {
+    fn default() -> Self {
+        SearchControl::new()
+    }
+}
+
+impl<Action> Clone for SearchControl<Action> {
+    fn clone(&self) -> Self {
+        SearchControl {
+            stop: self.stop.clone(),
+            progress: self.progress.clone(),
+        }
+    }
+}
+
+/// AI whose search runs in the background, so the caller stays responsive
+pub trait AsyncGameAi<Rules: GameRules> {
+    /// Starts searching the next move in `gamestate`. A running search is cancelled.
+    fn start_search(&mut self, gamestate: &Rules::State);
+
+    fn is_searching(&self) -> bool;
+
+    /// Progress of the running or the last search
+    fn progress(&self) -> SearchProgress<Rules::Action>;
+
+    /// The chosen move, once the search has finished on its own. Never blocks.
+    fn poll_move(&mut self) -> Option<Rules::Action>;
+
+    /// Stops the search early and waits for the best move found so far. None if no search is
+    /// running.
+    fn move_now(&mut self) -> Option<Rules::Action>;
+
+    /// Stops the search and discards its result
+    fn cancel(&mut self);
+
+    fn name(&self) -> String;
+}
+
+/// Runs the searches of any `GameAi` on a background thread, which owns the AI meanwhile.
+/// How quickly `move_now` and `cancel` return depends on how often the AI checks the
+/// `SearchControl` in `GameAi::determine_next_move_with_control`.
+pub struct BackgroundAi<Rules: GameRules, Ai> {
+    /// None while the AI is searching
+    ai: Option<Ai>,
+    /// Search thread, which hands back the AI with its move. Stopped and joined on drop.
+    search: Pondering<(Ai, Rules::Action)>,
+    control: SearchControl<Rules::Action>,
+    name: String,
+}
+
+impl<Rules: GameRules, Ai: GameAi<Rules>> BackgroundAi<Rules, Ai> {
+    pub fn new(ai: Ai) -> BackgroundAi<Rules, Ai> {
+        BackgroundAi {
+            name: ai.name(),
+            ai: Some(ai),
+            search: Pondering::default(),
+            control: SearchControl::new(),
+        }
+    }
+
+    /// The AI, unless it is searching
+    pub fn ai(&self) -> Option<&Ai> {
+        self.ai.as_ref()
+    }
+
+    /// The AI, unless it is searching, for example to start pondering
+    pub fn ai_mut(&mut self) -> Option<&mut Ai> {
+        self.ai.as_mut()
+    }
+}
+
+impl<Rules, Ai> BackgroundAi<Rules, Ai>
+where
+    Rules: GameRules,
+    Rules::Action: Send + 'static,
+    Ai: Send + 'static,
+{
+    /// Stops the search thread, waits for it and takes back the AI
+    fn join(&mut self) -> Option<Rules::Action> {
+        let (ai, action) = self.search.stop()?;
+        self.ai = Some(ai);
+        Some(action)
+    }
+}
+
+impl<Rules, Ai> AsyncGameAi<Rules> for BackgroundAi<Rules, Ai>
+where
+    Rules: GameRules + 'static,
+    Rules::State: Send,
+    Rules::Action: Send,
+    Ai: GameAi<Rules> + Send + 'static,
+{
+    fn start_search(&mut self, gamestate: &Rules::State) {
+        self.cancel();
+        let mut ai = self.ai.take().unwrap();
+        let gamestate = gamestate.clone();
+        self.control = SearchControl::new();
+        let control = self.control.clone();
+        self.search
+            .start_with_stop_flag(self.control.stop.clone(), move |_| {
+                let action = ai.determine_next_move_with_control(&gamestate, &control);
+                (ai, action)
+            });
+    }
+
+    fn is_searching(&self) -> bool {
+        self.search.is_running()
+    }
+
+    fn progress(&self) -> SearchProgress<Rules::Action> {
+        self.control.progress()
+    }
+
+    fn poll_move(&mut self) -> Option<Rules::Action> {
+        if self.search.is_finished() {
+            self.join()
+        } else {
+            None
+        }
+    }
+
+    fn move_now(&mut self) -> Option<Rules::Action> {
+        self.join()
+    }
+
+    fn cancel(&mut self) {
+        self.join();
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
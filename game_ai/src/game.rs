@@ -11,83 +11,125 @@ pub trait GameStateTrait<Action>: Default + Clone + core::fmt::Debug {
 
     fn next_player(&self) -> PlayerIndex;
 
-    /// Player which made the move resulting in this state
+    /// Player which made the move resulting in this state.
+    /// The default implementation is only correct for two player games.
     fn incoming_player(&self) -> PlayerIndex {
         self.next_player().opponent()
     }
+
+    /// Key identifying this state, e.g. for transposition tables.
+    /// Equal states must have equal keys, different states should differ with high probability.
+    /// Games which do not provide a key can not use search features which depend on it.
+    fn hash_key(&self) -> Option<u64> {
+        None
+    }
 }
 
+/// Largest number of players supported by `Rewards` and `PlayerIndex`
+pub const MAX_PLAYERS: usize = 6;
+
+/// A value for each player, indexed by `PlayerIndex`
 #[derive(Debug, PartialEq, Clone, Default)]
-pub struct Rewards {
-    pub player_0: f32,
-    pub player_1: f32,
-}
+pub struct Rewards([f32; MAX_PLAYERS]);
 
 impl Rewards {
+    /// Rewards of the players in order of their index, missing players get zero
+    pub fn new(rewards: &[f32]) -> Rewards {
+        assert!(rewards.len() <= MAX_PLAYERS);
+        let mut all_rewards = [0.0; MAX_PLAYERS];
+        all_rewards[..rewards.len()].copy_from_slice(rewards);
+        Rewards(all_rewards)
+    }
+
     pub fn for_player(&self, p: &PlayerIndex) -> f32 {
-        match p {
-            PlayerIndex::Zero => self.player_0,
-            PlayerIndex::One => self.player_1,
-        }
+        self.0[p.index()]
+    }
+
+    pub fn set_for_player(&mut self, p: &PlayerIndex, reward: f32) {
+        self.0[p.index()] = reward;
     }
 }
 
 impl std::ops::AddAssign<&Self> for Rewards {
     fn add_assign(&mut self, rhs: &Self) {
-        self.player_0 += rhs.player_0;
-        self.player_1 += rhs.player_1;
+        for (reward, rhs_reward) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *reward += rhs_reward;
+        }
     }
 }
 
-#[derive(PartialEq)]
-pub enum PlayerIndex {
-    Zero,
-    One,
-}
+/// Index of a player, in the order in which the players move at the start of the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PlayerIndex(usize);
+
 impl From<PlayerIndex> for usize {
     fn from(val: PlayerIndex) -> Self {
-        match val {
-            PlayerIndex::Zero => 0,
-            PlayerIndex::One => 1,
-        }
+        val.0
     }
 }
 
 impl PlayerIndex {
+    pub const ZERO: PlayerIndex = PlayerIndex(0);
+    pub const ONE: PlayerIndex = PlayerIndex(1);
+
+    pub fn new(index: usize) -> PlayerIndex {
+        assert!(index < MAX_PLAYERS, "At most {} players", MAX_PLAYERS);
+        PlayerIndex(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    /// Player moving after this one, if players take turns in order of their index
+    pub fn next(&self, n_players: usize) -> PlayerIndex {
+        PlayerIndex((self.0 + 1) % n_players)
+    }
+
+    /// Other player of a two player game
     pub fn opponent(&self) -> PlayerIndex {
-        match self {
-            PlayerIndex::Zero => PlayerIndex::One,
-            PlayerIndex::One => PlayerIndex::Zero,
-        }
+        assert!(self.0 < 2, "Only two player games have a single opponent");
+        PlayerIndex(1 - self.0)
     }
 
+    /// In two player games, player zero is maximizing and player one is minimizing the value
+    /// of player zero
     pub fn is_maximizing(&self) -> bool {
-        match self {
-            PlayerIndex::Zero => true,
-            PlayerIndex::One => false,
-        }
+        self.0 == 0
+    }
+
+    /// All players of a game with `n_players`
+    pub fn all(n_players: usize) -> impl Iterator<Item = PlayerIndex> {
+        (0..n_players).map(PlayerIndex::new)
     }
 }
 
 pub trait GameRules {
     type Action: core::fmt::Debug + std::hash::Hash + Eq + Clone;
     type State: GameStateTrait<Self::Action>;
-    const N_PLAYERS: u32;
+    const N_PLAYERS: usize;
 
     fn play(initial_state: &Self::State, action: &Self::Action) -> Self::State;
 
     // Play game until completion from a given state
     fn random_rollout(initial_state: &Self::State) -> Rewards {
+        Self::random_playout(initial_state).0
+    }
+
+    /// Random rollout which also returns the played actions, in order
+    fn random_playout(initial_state: &Self::State) -> (Rewards, Vec<Self::Action>) {
         let mut state: Self::State = initial_state.clone();
+        let mut actions = Vec::new();
         while !state.is_final() {
             let all_possible_actions = state.get_actions();
             let random_action = all_possible_actions
                 .choose(&mut rand::thread_rng())
                 .expect("Rollout failed, no actions possible!");
             state = Self::play(&state, random_action);
+            actions.push(random_action.clone());
         }
 
-        state.reward()
+        (state.reward(), actions)
     }
 }
 
@@ -95,6 +137,19 @@ pub trait GameRules {
 mod tests {
     use super::*;
 
+    #[test]
+    fn rewards_per_player() {
+        let mut rewards = Rewards::new(&[1.0, 0.0, 0.5]);
+        rewards += &Rewards::new(&[1.0, 1.0]);
+        assert_eq!(rewards.for_player(&PlayerIndex::ZERO), 2.0);
+        assert_eq!(rewards.for_player(&PlayerIndex::ONE), 1.0);
+        assert_eq!(rewards.for_player(&PlayerIndex::new(2)), 0.5);
+        assert_eq!(rewards.for_player(&PlayerIndex::new(5)), 0.0);
+
+        assert_eq!(PlayerIndex::new(2).next(3), PlayerIndex::ZERO);
+        assert_eq!(PlayerIndex::ONE.opponent(), PlayerIndex::ZERO);
+    }
+
     #[allow(unused)]
     fn do_stuff_with_game<Rules: GameRules>() {
         let initial_game_state = Rules::State::default();
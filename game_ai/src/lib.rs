@@ -1,5 +1,11 @@
 mod ai;
+mod background;
 mod game;
+mod model;
+mod ponder;
 
-pub use ai::{Evaluator, GameAi};
-pub use game::{GameRules, GameStateTrait, PlayerIndex, Rewards};
+pub use ai::{ActionHeuristic, Evaluator, GameAi, StopCondition};
+pub use background::{AsyncGameAi, BackgroundAi, SearchControl, SearchProgress};
+pub use game::{GameRules, GameStateTrait, PlayerIndex, Rewards, MAX_PLAYERS};
+pub use model::{LinearModel, ModelFeatures, PolicyValueModel, TrainingSample};
+pub use ponder::Pondering;
@@ -0,0 +1,194 @@
+//! Learned move priors and values, which searches can use instead of rollouts
+
+use crate::game::{GameRules, GameStateTrait, Rewards};
+
+/// Model of the probabilities of good moves and of the expected rewards of a state, as the
+/// policy and value network of AlphaZero
+pub trait PolicyValueModel {
+    type Rules: GameRules;
+
+    /// Probabilities of `actions`, which are all possible actions in the non-final `state`
+    fn policy(
+        &self,
+        state: &<Self::Rules as GameRules>::State,
+        actions: &[<Self::Rules as GameRules>::Action],
+    ) -> Vec<f32>;
+
+    /// Expected rewards of all players in the non-final `state`
+    fn value(&self, state: &<Self::Rules as GameRules>::State) -> Rewards;
+}
+
+/// Game specific features of states and actions for [LinearModel]
+pub trait ModelFeatures {
+    type Rules: GameRules;
+
+    const STATE_FEATURES: usize;
+    const ACTION_FEATURES: usize;
+
+    /// `STATE_FEATURES` features of the state, seen by the player to move
+    fn state_features(&self, state: &<Self::Rules as GameRules>::State) -> Vec<f32>;
+
+    /// `ACTION_FEATURES` features of an action of the player to move
+    fn action_features(
+        &self,
+        state: &<Self::Rules as GameRules>::State,
+        action: &<Self::Rules as GameRules>::Action,
+    ) -> Vec<f32>;
+}
+
+/// Position with the targets of a training step, usually from self-play
+#[derive(Clone, Debug)]
+pub struct TrainingSample<Rules: GameRules> {
+    pub state: Rules::State,
+    /// Probabilities of the actions to learn, such as the share of playouts of each root child.
+    /// Missing actions have probability zero.
+    pub policy: Vec<(Rules::Action, f32)>,
+    /// Rewards at the end of the game
+    pub rewards: Rewards,
+}
+
+/// Reference model for two player games, which runs on the CPU without dependencies: The value is
+/// the hyperbolic tangent of a linear function of the state features, and the policy the
+/// softmax of a linear function of the action features.
+#[derive(Clone, Debug)]
+pub struct LinearModel<Features> {
+    features: Features,
+    value_weights: Vec<f32>,
+    policy_weights: Vec<f32>,
+}
+
+impl<Features: ModelFeatures> LinearModel<Features> {
+    /// Untrained model: All states are a draw and all actions equally likely
+    pub fn new(features: Features) -> LinearModel<Features> {
+        LinearModel {
+            features,
+            value_weights: vec![0.0; Features::STATE_FEATURES],
+            policy_weights: vec![0.0; Features::ACTION_FEATURES],
+        }
+    }
+
+    /// Model with the weights of an earlier training
+    pub fn new_with_weights(
+        features: Features,
+        value_weights: Vec<f32>,
+        policy_weights: Vec<f32>,
+    ) -> LinearModel<Features> {
+        assert_eq!(value_weights.len(), Features::STATE_FEATURES);
+        assert_eq!(policy_weights.len(), Features::ACTION_FEATURES);
+        LinearModel {
+            features,
+            value_weights,
+            policy_weights,
+        }
+    }
+
+    pub fn value_weights(&self) -> &[f32] {
+        &self.value_weights
+    }
+
+    pub fn policy_weights(&self) -> &[f32] {
+        &self.policy_weights
+    }
+
+    /// Value between -1 and 1 for the player to move, with its gradient by the weights
+    fn value_with_gradient(
+        &self,
+        state: &<Features::Rules as GameRules>::State,
+    ) -> (f32, Vec<f32>) {
+        let features = self.features.state_features(state);
+        let value = dot(&self.value_weights, &features).tanh();
+        let gradient = features
+            .into_iter()
+            .map(|feature| (1.0 - value * value) * feature)
+            .collect();
+        (value, gradient)
+    }
+
+    /// One step of stochastic gradient descent on the squared error of the value and the cross
+    /// entropy of the policy. Returns the loss before the step.
+    pub fn train(&mut self, sample: &TrainingSample<Features::Rules>, learning_rate: f32) -> f32 {
+        let player = sample.state.next_player();
+        let target_value = 2.0 * sample.rewards.for_player(&player) - 1.0;
+        let (value, value_gradient) = self.value_with_gradient(&sample.state);
+        let value_error = value - target_value;
+        for (weight, gradient) in self.value_weights.iter_mut().zip(value_gradient) {
+            *weight -= learning_rate * 2.0 * value_error * gradient;
+        }
+
+        let actions = sample.state.get_actions();
+        let action_features = actions
+            .iter()
+            .map(|action| self.features.action_features(&sample.state, action))
+            .collect::<Vec<_>>();
+        let policy = softmax(&self.logits(&action_features));
+        let mut policy_loss = 0.0;
+        let mut policy_gradient = vec![0.0; Features::ACTION_FEATURES];
+        for ((action, features), probability) in actions.iter().zip(&action_features).zip(&policy) {
+            let target = sample
+                .policy
+                .iter()
+                .find(|(target_action, _)| target_action == action)
+                .map_or(0.0, |(_, target)| *target);
+            policy_loss -= target * probability.max(f32::MIN_POSITIVE).ln();
+            for (gradient, feature) in policy_gradient.iter_mut().zip(features) {
+                *gradient += (probability - target) * feature;
+            }
+        }
+        for (weight, gradient) in self.policy_weights.iter_mut().zip(policy_gradient) {
+            *weight -= learning_rate * gradient;
+        }
+
+        value_error * value_error + policy_loss
+    }
+
+    fn logits(&self, action_features: &[Vec<f32>]) -> Vec<f32> {
+        action_features
+            .iter()
+            .map(|features| dot(&self.policy_weights, features))
+            .collect()
+    }
+}
+
+impl<Features: ModelFeatures> PolicyValueModel for LinearModel<Features> {
+    type Rules = Features::Rules;
+
+    fn policy(
+        &self,
+        state: &<Features::Rules as GameRules>::State,
+        actions: &[<Features::Rules as GameRules>::Action],
+    ) -> Vec<f32> {
+        let action_features = actions
+            .iter()
+            .map(|action| self.features.action_features(state, action))
+            .collect::<Vec<_>>();
+        softmax(&self.logits(&action_features))
+    }
+
+    fn value(&self, state: &<Features::Rules as GameRules>::State) -> Rewards {
+        let player = state.next_player();
+        let reward = (1.0 + self.value_with_gradient(state).0) / 2.0;
+        let mut rewards = [0.0; 2];
+        rewards[player.index()] = reward;
+        rewards[player.opponent().index()] = 1.0 - reward;
+        Rewards::new(&rewards)
+    }
+}
+
+fn dot(weights: &[f32], features: &[f32]) -> f32 {
+    weights
+        .iter()
+        .zip(features)
+        .map(|(weight, feature)| weight * feature)
+        .sum()
+}
+
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    // Subtracting the maximum keeps the exponentials finite
+    let max_logit = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let weights = logits
+        .iter()
+        .map(|logit| (logit - max_logit).exp())
+        .collect::<Vec<_>>();
+    let sum: f32 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / sum).collect()
+}
@@ -0,0 +1,100 @@
+//! Background searches while the opponent thinks
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Search running on a background thread until it is stopped, which then hands back its result,
+/// such as the search tree. Dropping it stops the search and discards the result. Clones are idle.
+pub struct Pondering<T> {
+    running: Option<(Arc<AtomicBool>, JoinHandle<T>)>,
+}
+
+impl<T: Send + 'static> Pondering<T> {
+    /// Stops the current search, if any, and runs `search` on a new thread. The search should
+    /// return once the flag it is passed becomes true.
+    pub fn start<Search>(&mut self, search: Search)
+    where
+        Search: FnOnce(&AtomicBool) -> T + Send + 'static,
+    {
+        self.start_with_stop_flag(Arc::new(AtomicBool::new(false)), search);
+    }
+
+    /// Like `start`, with a stop flag which the caller may share, for example with a
+    /// `SearchControl`
+    pub fn start_with_stop_flag<Search>(&mut self, stop: Arc<AtomicBool>, search: Search)
+    where
+        Search: FnOnce(&AtomicBool) -> T + Send + 'static,
+    {
+        self.stop();
+        let thread_stop = stop.clone();
+        let handle = std::thread::spawn(move || search(&thread_stop));
+        self.running = Some((stop, handle));
+    }
+}
+
+impl<T> Pondering<T> {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Whether the search has returned on its own, so that `stop` does not wait
+    pub fn is_finished(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|(_, handle)| handle.is_finished())
+    }
+
+    /// Stops the search and waits for its result. None if no search was running.
+    pub fn stop(&mut self) -> Option<T> {
+        let (stop, handle) = self.running.take()?;
+        stop.store(true, Ordering::Relaxed);
+        Some(handle.join().expect("Pondering thread panicked"))
+    }
+}
+
+impl<T> Default for Pondering<T> {
+    fn default() -> Self {
+        Pondering { running: None }
+    }
+}
+
+impl<T> Clone for Pondering<T> {
+    fn clone(&self) -> Self {
+        Pondering::default()
+    }
+}
+
+impl<T> Drop for Pondering<T> {
+    fn drop(&mut self) {
+        if let Some((stop, handle)) = self.running.take() {
+            stop.store(true, Ordering::Relaxed);
+            // A panic of the search must not abort the program while unwinding
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::Pondering;
+
+    #[test]
+    fn search_runs_until_stopped() {
+        let mut pondering = Pondering::default();
+        assert!(pondering.stop().is_none());
+
+        pondering.start(|stop| {
+            let mut iterations = 0u64;
+            while !stop.load(Ordering::Relaxed) || iterations == 0 {
+                iterations += 1;
+            }
+            iterations
+        });
+        assert!(pondering.is_running());
+        assert!(pondering.stop().unwrap() > 0);
+        assert!(!pondering.is_running());
+    }
+}
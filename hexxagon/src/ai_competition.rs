@@ -1,12 +1,13 @@
 use std::time::Duration;
 
-use game_ai::GameAi;
+use game_ai::{GameAi, SearchControl, StopCondition};
 use hexxagon_lib::{
-    ai::HexxagonEvaluator,
+    ai::{CaptureHeuristic, HexxagonEvaluator},
     game::{self, rules::HexxagonRules, GameResult, GameState, MoveResult, Player},
 };
 use indicatif::ProgressIterator;
-use mcts::GenericMonteCarloTreeSearchAi;
+use itertools::Itertools;
+use mcts::{GenericMonteCarloTreeSearchAi, Parallelism};
 use minimax::MiniMax;
 
 fn play_game<PearlsAI: GameAi<HexxagonRules>, RubiesAI: GameAi<HexxagonRules>>(
@@ -20,7 +21,18 @@ fn play_game<PearlsAI: GameAi<HexxagonRules>, RubiesAI: GameAi<HexxagonRules>>(
             game::Player::Rubies => &mut rubies_ai,
             game::Player::Pearls => &mut pearls_ai,
         };
-        let ai_move = ai.determine_next_move(&gamestate);
+        let control = SearchControl::new();
+        let ai_move = ai.determine_next_move_with_control(&gamestate, &control);
+        let progress = control.progress();
+        println!(
+            "{:?}: {} (value {}, principal variation {})",
+            gamestate.next_player(),
+            ai_move,
+            progress
+                .value
+                .map_or("unknown".to_string(), |value| format!("{:.2}", value)),
+            progress.principal_variation.iter().join(", ")
+        );
         let result = gamestate.player_move(ai_move.src, ai_move.dst);
         assert_eq!(result, MoveResult::Success);
         i += 1;
@@ -33,11 +45,19 @@ fn main() {
     let mut rubies_wins = 0;
     let mut pearls_wins = 0;
 
-    let create_rubies_ai = || MiniMax::new(3, HexxagonEvaluator {});
+    let time_per_move = Duration::from_millis(1000);
+    let create_rubies_ai = || {
+        MiniMax::new_iterative_deepening(
+            StopCondition::Time(time_per_move),
+            HexxagonEvaluator::default(),
+        )
+        .with_action_heuristic(CaptureHeuristic {})
+        .with_quiescence(4)
+    };
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
     let create_pearls_ai = || {
-        GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(mcts::StopCondition::Time(
-            Duration::from_millis(1000),
-        ))
+        GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Time(time_per_move))
+            .with_parallelism(Parallelism::SharedTree { threads })
     };
 
     for _i in (0..20).progress() {
@@ -1,8 +1,6 @@
-use std::time::Instant;
-
-use game_ai::GameAi;
+use game_ai::{AsyncGameAi, BackgroundAi, GameAi, StopCondition};
 use ggez::glam::Vec2;
-use hexxagon_lib::ai::HexxagonEvaluator;
+use hexxagon_lib::ai::{HexxagonEvaluator, HexxagonMove};
 use hexxagon_lib::game::CellState;
 use hexxagon_lib::game::GameResult;
 use hexxagon_lib::game::GameState;
@@ -14,11 +12,14 @@ use ggez::graphics::DrawParam;
 use ggez::graphics::TextLayout;
 use ggez::graphics::{self, Color};
 use ggez::Context;
-use hexxagon_lib::game::rules::HexxagonRules;
 use hexxagon_lib::hexgrid::AxialVector;
 
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use hexxagon_lib::game::rules::HexxagonRules;
 use hexxagon_lib::game::MoveResult;
-use minimax::MiniMax;
+use itertools::Itertools;
+use minimax::{MiniMax, ReplacementPolicy};
+use std::time::Duration;
 
 enum UIState {
     SelectingSource,
@@ -31,7 +32,8 @@ struct MainState {
     board_position: Vec2,
     cell_size: f32,
     cell_aspect_ratio: f32,
-    ai: Box<dyn GameAi<HexxagonRules>>,
+    /// Searches on a background thread, so the window stays responsive
+    ai: BackgroundAi<HexxagonRules, Box<dyn GameAi<HexxagonRules> + Send>>,
 }
 
 impl MainState {
@@ -42,7 +44,15 @@ impl MainState {
             cell_size: 40.0,
             cell_aspect_ratio: 0.5,
             board_position: Vec2::new(0.0, 0.0),
-            ai: Box::new(MiniMax::new(3, HexxagonEvaluator {})),
+            // Pondering fills the transposition table while the human thinks, so the search
+            // reaches deeper within the same time
+            ai: BackgroundAi::new(Box::new(
+                MiniMax::new_iterative_deepening(
+                    StopCondition::Time(Duration::from_secs(1)),
+                    HexxagonEvaluator::default(),
+                )
+                .with_transposition_table(64 << 20, ReplacementPolicy::DepthPreferred),
+            )),
         }
     }
 }
@@ -66,6 +76,32 @@ impl MainState {
     fn is_in_gameplay_state(&self) -> bool {
         self.gamestate.result().is_none()
     }
+
+    /// Plays the move of the finished background search, then ponders during the human's turn
+    fn play_ai_move(&mut self, ai_move: HexxagonMove) {
+        let progress = self.ai.progress();
+        let ai_move_result = self.gamestate.player_move(ai_move.src, ai_move.dst);
+        assert_eq!(ai_move_result, MoveResult::Success);
+        println!(
+            "AI ({}) made move: {} (depth {}, {} nodes, value {}, principal variation {})",
+            self.ai.name(),
+            ai_move,
+            progress.depth,
+            progress.nodes,
+            progress
+                .value
+                .map_or("unknown".to_string(), |value| format!("{:.2}", value)),
+            progress.principal_variation.iter().join(", ")
+        );
+
+        if self.gamestate.result().is_none() {
+            let gamestate = &self.gamestate;
+            self.ai
+                .ai_mut()
+                .expect("AI is idle after its search")
+                .start_pondering(gamestate);
+        }
+    }
 }
 
 fn draw_field(
@@ -134,7 +170,9 @@ fn draw_field(
 
 impl event::EventHandler<ggez::GameError> for MainState {
     fn update(&mut self, _ctx: &mut Context) -> ggez::GameResult {
-        // TODO: Input handling?
+        if let Some(ai_move) = self.ai.poll_move() {
+            self.play_ai_move(ai_move);
+        }
         Ok(())
     }
 
@@ -144,7 +182,15 @@ impl event::EventHandler<ggez::GameError> for MainState {
 
         draw_field(ctx, &mut canvas, self)?;
 
-        let fps_text = graphics::Text::new(format!("{} FPS", ctx.time.fps()));
+        let mut status = format!("{} FPS", ctx.time.fps());
+        if self.ai.is_searching() {
+            let progress = self.ai.progress();
+            status += &format!(
+                "\nAI thinking (depth {}, {} nodes), press space to move now",
+                progress.depth, progress.nodes
+            );
+        }
+        let fps_text = graphics::Text::new(status);
         canvas.draw(&fps_text, DrawParam::new().color(Color::WHITE));
 
         let score = self.gamestate.scores();
@@ -195,6 +241,10 @@ impl event::EventHandler<ggez::GameError> for MainState {
             println!("Clicked button but not in gameplay state");
             return Ok(());
         }
+        if self.ai.is_searching() {
+            println!("Clicked button while the AI is thinking");
+            return Ok(());
+        }
 
         // Get axial coordinate from cartesian
         let x = x - self.board_position.x;
@@ -219,18 +269,8 @@ impl event::EventHandler<ggez::GameError> for MainState {
                 let player_move_result = self.gamestate.player_move(source, axial_coordinate); // TODO: Display result
 
                 if self.gamestate.result().is_none() && player_move_result == MoveResult::Success {
-                    let start = Instant::now();
-                    let ai_move = self.ai.determine_next_move(&self.gamestate);
-                    let duration = start.elapsed();
-                    let ai_move_result = self.gamestate.player_move(ai_move.src, ai_move.dst);
-                    assert_eq!(ai_move_result, MoveResult::Success);
-
-                    println!(
-                        "AI ({}) made move: {:?} ({:?})",
-                        self.ai.name(),
-                        ai_move,
-                        duration
-                    )
+                    // Stops pondering
+                    self.ai.start_search(&self.gamestate);
                 }
 
                 UIState::SelectingSource
@@ -239,6 +279,24 @@ impl event::EventHandler<ggez::GameError> for MainState {
 
         Ok(())
     }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> ggez::GameResult {
+        match input.keycode {
+            Some(KeyCode::Space) => {
+                if let Some(ai_move) = self.ai.move_now() {
+                    self.play_ai_move(ai_move);
+                }
+            }
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 fn main() -> ggez::GameResult {
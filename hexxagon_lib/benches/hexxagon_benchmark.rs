@@ -1,6 +1,13 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use game_ai::GameRules;
-use hexxagon_lib::game::{rules::HexxagonRules, GameState};
+use hexxagon_lib::{
+    ai::move_generation,
+    game::{
+        bitboard::{BitboardRules, BitboardState},
+        rules::HexxagonRules,
+        GameState,
+    },
+};
 
 fn hexxagon_rollout(c: &mut Criterion) {
     let initial_state = GameState::default();
@@ -9,5 +16,24 @@ fn hexxagon_rollout(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, hexxagon_rollout);
+fn hexxagon_all_moves(c: &mut Criterion) {
+    let initial_state = GameState::default();
+    c.bench_function("hexxagon_all_moves", |b| {
+        b.iter(|| move_generation::all_moves(&initial_state))
+    });
+}
+
+fn hexxagon_bitboard_rollout(c: &mut Criterion) {
+    let initial_state = BitboardState::default();
+    c.bench_function("hexxagon_bitboard_rollout", |b| {
+        b.iter(|| <BitboardRules as GameRules>::random_rollout(&initial_state))
+    });
+}
+
+criterion_group!(
+    benches,
+    hexxagon_rollout,
+    hexxagon_all_moves,
+    hexxagon_bitboard_rollout
+);
 criterion_main!(benches);
@@ -1,8 +1,8 @@
 pub mod move_generation;
-use game_ai::Evaluator;
+use game_ai::{ActionHeuristic, Evaluator, ModelFeatures, PlayerIndex};
 
 use crate::{
-    game::{rules::HexxagonRules, GameState},
+    game::{rules::HexxagonRules, CellState, GameState},
     hexgrid::AxialVector,
 };
 
@@ -12,15 +12,105 @@ pub struct HexxagonMove {
     pub dst: AxialVector,
 }
 
+impl HexxagonMove {
+    /// Clone moves keep the piece at the source, jumps move it
+    pub fn is_clone(&self) -> bool {
+        (self.dst - self.src).length() == 1
+    }
+}
+
+impl std::fmt::Display for HexxagonMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.src, self.dst)
+    }
+}
+
 #[derive(Clone)]
-pub struct HexxagonEvaluator {}
+pub struct HexxagonEvaluator {
+    /// Moves converting at least this many opponent pieces are noisy, 3 by default
+    pub noisy_captures: usize,
+}
+
+impl Default for HexxagonEvaluator {
+    fn default() -> Self {
+        HexxagonEvaluator { noisy_captures: 3 }
+    }
+}
 
 impl Evaluator for HexxagonEvaluator {
     type Rules = HexxagonRules;
 
-    fn value(&self, state: &GameState) -> f32 {
+    fn value(&self, state: &GameState, player: &PlayerIndex) -> f32 {
         let scores = state.scores();
-        // Rubies: Zero, maximizing
-        scores.rubies as f32 - scores.pearls as f32
+        // Rubies: Zero
+        let rubies_lead = scores.rubies as f32 - scores.pearls as f32;
+        if *player == PlayerIndex::ZERO {
+            rubies_lead
+        } else {
+            -rubies_lead
+        }
+    }
+
+    fn is_noisy(&self, state: &GameState, action: &HexxagonMove) -> bool {
+        move_generation::captures(state, action) >= self.noisy_captures
+    }
+}
+
+/// Scores moves by how much they increase the difference in pieces: Two for every converted
+/// opponent piece, plus one for a clone move.
+#[derive(Clone)]
+pub struct CaptureHeuristic {}
+
+impl ActionHeuristic for CaptureHeuristic {
+    type Rules = HexxagonRules;
+
+    fn score(&self, state: &GameState, action: &HexxagonMove) -> f32 {
+        let captures = move_generation::captures(state, action) as f32;
+        2.0 * captures + if action.is_clone() { 1.0 } else { 0.0 }
+    }
+}
+
+/// Features for a [game_ai::LinearModel]: The shares of the board held by both players, and for
+/// moves whether they clone, their captures and the own pieces next to the destination
+#[derive(Clone)]
+pub struct HexxagonFeatures {}
+
+impl ModelFeatures for HexxagonFeatures {
+    type Rules = HexxagonRules;
+
+    const STATE_FEATURES: usize = 4;
+    const ACTION_FEATURES: usize = 3;
+
+    fn state_features(&self, state: &GameState) -> Vec<f32> {
+        let player = state.next_player();
+        let mut own = 0.0;
+        let mut opponent = 0.0;
+        let mut cells = 0.0;
+        for (_position, cell) in state.get_field().tile_iter() {
+            match cell {
+                CellState::Occupied(owner) if *owner == player => own += 1.0,
+                CellState::Occupied(_) => opponent += 1.0,
+                CellState::Empty => {}
+                CellState::Blocked => continue,
+            }
+            cells += 1.0;
+        }
+        vec![1.0, own / cells, opponent / cells, (own - opponent) / cells]
+    }
+
+    fn action_features(&self, state: &GameState, action: &HexxagonMove) -> Vec<f32> {
+        let own = CellState::Occupied(state.next_player());
+        let field = state.get_field();
+        let (source, destination) = (field.index(action.src), field.index(action.dst));
+        let neighbors = field
+            .neighbors(destination.unwrap())
+            .iter()
+            .filter(|neighbor| Some(**neighbor) != source && *field.cell(**neighbor) == own)
+            .count();
+        vec![
+            if action.is_clone() { 1.0 } else { 0.0 },
+            move_generation::captures(state, action) as f32 / 6.0,
+            neighbors as f32 / 6.0,
+        ]
     }
 }
@@ -3,105 +3,135 @@ use rand::{seq::SliceRandom, Rng};
 use crate::{
     ai::HexxagonMove,
     game::{CellState, GameState},
-    hexgrid::AxialVector,
+    hexgrid::HexGrid,
 };
 use smallvec::SmallVec;
 
-static POSSIBLE_MOVES: [AxialVector; 6 + 12] = [
-    // Distance 1
-    AxialVector::new(-1, 1),
-    AxialVector::new(0, 1),
-    AxialVector::new(1, 0),
-    AxialVector::new(1, -1),
-    AxialVector::new(0, -1),
-    AxialVector::new(-1, 0),
-    // Distance 2
-    AxialVector::new(-2, 2),
-    AxialVector::new(-1, 2),
-    AxialVector::new(0, 2),
-    AxialVector::new(1, 1),
-    AxialVector::new(2, 0),
-    AxialVector::new(2, -1),
-    AxialVector::new(2, -2),
-    AxialVector::new(1, -2),
-    AxialVector::new(0, -2),
-    AxialVector::new(-1, -1),
-    AxialVector::new(-2, 0),
-    AxialVector::new(-2, 1),
-];
+/// Indices of the empty cells a piece at `source` can move to
+fn destinations(field: &HexGrid<CellState>, source: usize) -> impl Iterator<Item = usize> + '_ {
+    field
+        .neighbors(source)
+        .iter()
+        .chain(field.neighbors_at_distance_2(source))
+        .copied()
+        .filter(|destination| *field.cell(*destination) == CellState::Empty)
+}
 
 /// Returns a **random** valid move from the given state
 #[allow(unused)]
 pub fn sample_valid_move(gamestate: &GameState) -> HexxagonMove {
     assert!(gamestate.result().is_none()); // Ensures a move can be found
 
-    let mut possible_sources: SmallVec<[AxialVector; 32]> = gamestate
-        // let mut possible_sources: Vec::<_> = gamestate
-        .get_field()
-        .tile_iter()
-        .filter(|(_pos, state)| **state == CellState::Occupied(gamestate.next_player()))
-        .map(|(pos, _state)| *pos)
+    let field = gamestate.get_field();
+    let own = CellState::Occupied(gamestate.next_player());
+    let mut possible_sources: SmallVec<[usize; 32]> = field
+        .cells()
+        .iter()
+        .enumerate()
+        .filter(|(_index, state)| **state == own)
+        .map(|(index, _state)| index)
         .collect();
 
     let mut rng = rand::thread_rng();
 
-    let mut selected_source = None;
-    let mut selected_destination = None;
-
     loop {
         // Select random source
         let source_index = rng.gen_range(0..possible_sources.len());
-        selected_source = Some(possible_sources.swap_remove(source_index));
+        let source = possible_sources.swap_remove(source_index);
 
         // Collect possible destinations
-        let mut possible_destinations = SmallVec::<[AxialVector; 18]>::new();
-
-        for move_vec in POSSIBLE_MOVES {
-            let hex = selected_source.unwrap() + move_vec;
-            if gamestate.get_field().is_in_bounds(hex)
-                && *gamestate.get_field().get(hex).unwrap() == CellState::Empty
-            {
-                possible_destinations.push(hex);
-            }
-        }
+        let possible_destinations: SmallVec<[usize; 18]> = destinations(field, source).collect();
 
-        if possible_destinations.is_empty() {
-            // No move possible from this source
-            continue;
+        // Select random destination, if a move is possible from this source
+        if let Some(destination) = possible_destinations.choose(&mut rng) {
+            return HexxagonMove {
+                src: field.coordinate(source),
+                dst: field.coordinate(*destination),
+            };
         }
-
-        // Select random destination
-        selected_destination = Some(*possible_destinations.choose(&mut rng).unwrap());
-        break;
     }
-    assert!(selected_source.is_some());
-    assert!(selected_destination.is_some());
+}
 
-    HexxagonMove {
-        src: selected_source.unwrap(),
-        dst: selected_destination.unwrap(),
-    }
+/// Number of opponent pieces which would be converted by the move
+pub fn captures(state: &GameState, hexxagon_move: &HexxagonMove) -> usize {
+    let opponent = CellState::Occupied(state.next_player().opponent());
+    let field = state.get_field();
+    let Some(destination) = field.index(hexxagon_move.dst) else {
+        return 0;
+    };
+    field
+        .neighbors(destination)
+        .iter()
+        .filter(|neighbor| *field.cell(**neighbor) == opponent)
+        .count()
 }
 
 pub fn all_moves(state: &GameState) -> Vec<HexxagonMove> {
+    let field = state.get_field();
+    let own = CellState::Occupied(state.next_player());
     let mut moves = vec![];
-    for (source_pos, source_state) in state.get_field().tile_iter() {
-        if *source_state != CellState::Occupied(state.next_player()) {
+    for (source, source_state) in field.cells().iter().enumerate() {
+        if *source_state != own {
             continue;
         }
 
-        for move_vec in POSSIBLE_MOVES {
-            let target = *source_pos + move_vec;
-            if state.get_field().is_in_bounds(target)
-                && *state.get_field().get(target).unwrap() == CellState::Empty
-            {
-                moves.push(HexxagonMove {
-                    src: *source_pos,
-                    dst: target,
-                })
-            }
+        for target in destinations(field, source) {
+            moves.push(HexxagonMove {
+                src: field.coordinate(source),
+                dst: field.coordinate(target),
+            })
         }
     }
 
     moves
 }
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashSet;
+
+    use super::{all_moves, sample_valid_move};
+    use crate::{
+        ai::HexxagonMove,
+        game::{CellState, GameState},
+        hexgrid::AxialVector,
+    };
+
+    /// Moves to all empty cells within distance two of own pieces, by their coordinates
+    fn naive_moves(state: &GameState) -> FxHashSet<HexxagonMove> {
+        let field = state.get_field();
+        let mut moves = FxHashSet::default();
+        for (src, cell) in field.tile_iter() {
+            if *cell != CellState::Occupied(state.next_player()) {
+                continue;
+            }
+            for q in -2..=2 {
+                for r in -2..=2 {
+                    let dst = *src + AxialVector::new(q, r);
+                    let distance = (dst - *src).length();
+                    if (1..=2).contains(&distance) && field.get(dst) == Some(&CellState::Empty) {
+                        moves.insert(HexxagonMove { src: *src, dst });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    #[test]
+    fn neighbor_tables_find_all_moves() {
+        let mut state = GameState::initialize();
+        while state.result().is_none() {
+            let moves = all_moves(&state);
+            assert_eq!(
+                moves.iter().cloned().collect::<FxHashSet<_>>(),
+                naive_moves(&state)
+            );
+            assert_eq!(moves.len(), naive_moves(&state).len());
+
+            let random_move = sample_valid_move(&state);
+            assert!(moves.contains(&random_move));
+            state.player_move(random_move.src, random_move.dst);
+        }
+    }
+}
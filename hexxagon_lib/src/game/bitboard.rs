@@ -0,0 +1,365 @@
+//! Hexxagon positions as bitboards: Every cell of the board is one bit of a `u128` mask, so moves,
+//! captures and the end of the game are computed with a few mask operations.
+//!
+//! The cells are numbered row by row, like the indices of [HexGrid].
+
+use rand::{seq::SliceRandom, Rng};
+use smallvec::SmallVec;
+
+use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards};
+
+use super::{zobrist, CellState, GameResult, GameState, MoveResult, Player, Scores};
+use crate::ai::HexxagonMove;
+use crate::hexgrid::{AxialVector, HexGrid, BOARD_SIZE, CELL_COUNT};
+
+/// Largest distance of a cell from the center of the board
+const RADIUS: i32 = BOARD_SIZE - 1;
+const ALL_CELLS: u128 = (1 << CELL_COUNT) - 1;
+
+/// Index of the first cell of every row, from `r = -RADIUS` to `r = RADIUS`
+static ROW_STARTS: [usize; 2 * RADIUS as usize + 1] = row_starts();
+static COORDINATES: [AxialVector; CELL_COUNT] = coordinates();
+/// Cells at distance one of every cell, which clone moves reach and captures convert
+static NEIGHBORS: [u128; CELL_COUNT] = neighbor_masks(1);
+/// Cells at distance two of every cell, which jump moves reach
+static JUMPS: [u128; CELL_COUNT] = neighbor_masks(2);
+
+const fn row_length(r: i32) -> usize {
+    (2 * RADIUS + 1 - r.abs()) as usize
+}
+
+/// Smallest coordinate `q` of the cells in row `r`
+const fn row_q_start(r: i32) -> i32 {
+    if r < 0 {
+        -RADIUS - r
+    } else {
+        -RADIUS
+    }
+}
+
+const fn row_starts() -> [usize; 2 * RADIUS as usize + 1] {
+    let mut starts = [0; 2 * RADIUS as usize + 1];
+    let mut row = 1;
+    while row < starts.len() {
+        starts[row] = starts[row - 1] + row_length(row as i32 - 1 - RADIUS);
+        row += 1;
+    }
+    starts
+}
+
+/// Coordinates `(q, r)` of every cell
+const fn axial_coordinates() -> [(i32, i32); CELL_COUNT] {
+    let mut coordinates = [(0, 0); CELL_COUNT];
+    let mut index = 0;
+    let mut r = -RADIUS;
+    while r <= RADIUS {
+        let mut q = row_q_start(r);
+        while index < CELL_COUNT && q < row_q_start(r) + row_length(r) as i32 {
+            coordinates[index] = (q, r);
+            index += 1;
+            q += 1;
+        }
+        r += 1;
+    }
+    coordinates
+}
+
+const fn coordinates() -> [AxialVector; CELL_COUNT] {
+    let axial = axial_coordinates();
+    let mut coordinates = [AxialVector::new(0, 0); CELL_COUNT];
+    let mut index = 0;
+    while index < CELL_COUNT {
+        coordinates[index] = AxialVector::new(axial[index].0, axial[index].1);
+        index += 1;
+    }
+    coordinates
+}
+
+const fn neighbor_masks(distance: i32) -> [u128; CELL_COUNT] {
+    let axial = axial_coordinates();
+    let mut masks = [0; CELL_COUNT];
+    let mut cell = 0;
+    while cell < CELL_COUNT {
+        let mut other = 0;
+        while other < CELL_COUNT {
+            let q = axial[other].0 - axial[cell].0;
+            let r = axial[other].1 - axial[cell].1;
+            if (q.abs() + r.abs() + (q + r).abs()) / 2 == distance {
+                masks[cell] |= 1 << other;
+            }
+            other += 1;
+        }
+        cell += 1;
+    }
+    masks
+}
+
+/// Index of the cell at `coordinate`, if it is on the board
+fn cell_index(coordinate: AxialVector) -> Option<usize> {
+    if coordinate.length() > RADIUS {
+        return None;
+    }
+    let row = (coordinate.r() + RADIUS) as usize;
+    Some(ROW_STARTS[row] + (coordinate.q() - row_q_start(coordinate.r())) as usize)
+}
+
+/// Indices of the set bits of `mask`, from lowest to highest
+fn cells(mut mask: u128) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let cell = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(cell)
+    })
+}
+
+/// Same position as [GameState], with the pieces of each player and the blocked cells as masks
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitboardState {
+    /// Pieces of the Rubies and of the Pearls
+    pieces: [u128; 2],
+    blocked: u128,
+    next_move: Player,
+    /// Zobrist hash, equal to the one of the same [GameState]
+    hash: u64,
+}
+
+impl BitboardState {
+    pub fn initialize() -> BitboardState {
+        BitboardState::from(&GameState::initialize())
+    }
+
+    pub fn next_player(&self) -> Player {
+        self.next_move
+    }
+
+    /// Key of this state, which is the same as for the equal [GameState]
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn empty(&self) -> u128 {
+        ALL_CELLS & !(self.pieces[0] | self.pieces[1] | self.blocked)
+    }
+
+    /// Empty cells the piece at `source` can move to
+    fn destinations(&self, source: usize) -> u128 {
+        (NEIGHBORS[source] | JUMPS[source]) & self.empty()
+    }
+
+    /// Adds or removes pieces of `player` on all cells of `mask`
+    fn toggle(&mut self, player: Player, mask: u128) {
+        self.pieces[player.index()] ^= mask;
+        let cell_state = CellState::Occupied(player);
+        for cell in cells(mask) {
+            self.hash ^= zobrist::cell_key(cell, &cell_state);
+        }
+    }
+
+    pub fn player_move(&mut self, from: AxialVector, to: AxialVector) -> MoveResult {
+        let player = self.next_move;
+
+        let move_length = (to - from).length();
+        if !(1..=2).contains(&move_length) {
+            return MoveResult::Fail;
+        }
+        let (Some(from), Some(to)) = (cell_index(from), cell_index(to)) else {
+            return MoveResult::Fail;
+        };
+        if self.pieces[player.index()] & (1 << from) == 0 || self.empty() & (1 << to) == 0 {
+            return MoveResult::Fail;
+        }
+
+        self.toggle(player, 1 << to);
+        if move_length == 2 {
+            self.toggle(player, 1 << from);
+        }
+        let captured = NEIGHBORS[to] & self.pieces[player.opponent().index()];
+        self.toggle(player.opponent(), captured);
+        self.toggle(player, captured);
+
+        self.hash ^= zobrist::next_player_key(player) ^ zobrist::next_player_key(player.opponent());
+        self.next_move = player.opponent();
+
+        MoveResult::Success
+    }
+
+    pub fn scores(&self) -> Scores {
+        Scores {
+            rubies: self.pieces[Player::Rubies.index()].count_ones() as i32,
+            pearls: self.pieces[Player::Pearls.index()].count_ones() as i32,
+        }
+    }
+
+    /// The game ends once the player to move can not reach any empty cell
+    pub fn result(&self) -> Option<GameResult> {
+        let reachable = cells(self.pieces[self.next_move.index()]).fold(0, |reachable, source| {
+            reachable | NEIGHBORS[source] | JUMPS[source]
+        });
+        if reachable & self.empty() != 0 {
+            return None;
+        }
+        let scores = self.scores();
+        match scores.pearls.cmp(&scores.rubies) {
+            std::cmp::Ordering::Less => Some(GameResult::Win(Player::Rubies)),
+            std::cmp::Ordering::Equal => Some(GameResult::Tie),
+            std::cmp::Ordering::Greater => Some(GameResult::Win(Player::Pearls)),
+        }
+    }
+
+    /// All moves of the player to move, in the order of their source cell
+    pub fn all_moves(&self) -> Vec<HexxagonMove> {
+        cells(self.pieces[self.next_move.index()])
+            .flat_map(|source| {
+                cells(self.destinations(source)).map(move |destination| HexxagonMove {
+                    src: COORDINATES[source],
+                    dst: COORDINATES[destination],
+                })
+            })
+            .collect()
+    }
+
+    /// Random move: A random source among those with a move, then a random destination
+    pub fn sample_valid_move(&self) -> HexxagonMove {
+        let sources: SmallVec<[(usize, u128); 64]> = cells(self.pieces[self.next_move.index()])
+            .map(|source| (source, self.destinations(source)))
+            .filter(|(_, destinations)| *destinations != 0)
+            .collect();
+        let mut rng = rand::thread_rng();
+        let (source, destinations) = *sources.choose(&mut rng).expect("No move possible");
+        let destination = cells(destinations)
+            .nth(rng.gen_range(0..destinations.count_ones() as usize))
+            .unwrap();
+        HexxagonMove {
+            src: COORDINATES[source],
+            dst: COORDINATES[destination],
+        }
+    }
+}
+
+impl From<&GameState> for BitboardState {
+    fn from(state: &GameState) -> Self {
+        let mut bitboard = BitboardState {
+            pieces: [0; 2],
+            blocked: 0,
+            next_move: state.next_player(),
+            hash: state.zobrist_hash(),
+        };
+        for (coordinate, cell) in state.get_field().tile_iter() {
+            let bit = 1 << cell_index(*coordinate).expect("Board has radius 5");
+            match cell {
+                CellState::Empty => {}
+                CellState::Occupied(player) => bitboard.pieces[player.index()] |= bit,
+                CellState::Blocked => bitboard.blocked |= bit,
+            }
+        }
+        bitboard
+    }
+}
+
+impl From<&BitboardState> for GameState {
+    fn from(bitboard: &BitboardState) -> Self {
+        let mut field = HexGrid::new_fill(BOARD_SIZE, CellState::Empty);
+        for (player, pieces) in [Player::Rubies, Player::Pearls].iter().zip(bitboard.pieces) {
+            for cell in cells(pieces) {
+                *field.get_mut(COORDINATES[cell]).unwrap() = CellState::Occupied(*player);
+            }
+        }
+        for cell in cells(bitboard.blocked) {
+            *field.get_mut(COORDINATES[cell]).unwrap() = CellState::Blocked;
+        }
+        GameState::from_field(field, bitboard.next_move)
+    }
+}
+
+impl Default for BitboardState {
+    fn default() -> Self {
+        Self::initialize()
+    }
+}
+
+impl GameStateTrait<HexxagonMove> for BitboardState {
+    fn is_final(&self) -> bool {
+        self.result().is_some()
+    }
+
+    fn get_actions(&self) -> Vec<HexxagonMove> {
+        self.all_moves()
+    }
+
+    fn reward(&self) -> Rewards {
+        Rewards::from(self.result().unwrap())
+    }
+
+    fn next_player(&self) -> PlayerIndex {
+        PlayerIndex::from(self.next_move)
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+/// Same rules as [super::rules::HexxagonRules], on bitboards
+#[derive(Clone)]
+pub struct BitboardRules {}
+
+impl GameRules for BitboardRules {
+    type State = BitboardState;
+    type Action = HexxagonMove;
+
+    const N_PLAYERS: usize = 2;
+
+    fn play(initial_state: &Self::State, action: &Self::Action) -> Self::State {
+        let mut new_state = initial_state.clone();
+        new_state.player_move(action.src, action.dst);
+        new_state
+    }
+
+    fn random_playout(initial_state: &Self::State) -> (Rewards, Vec<Self::Action>) {
+        let mut state = initial_state.clone();
+        let mut moves = Vec::new();
+        while !state.is_final() {
+            let random_move = state.sample_valid_move();
+            state.player_move(random_move.src, random_move.dst);
+            moves.push(random_move);
+        }
+
+        (state.reward(), moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cell_index, BitboardState, ALL_CELLS, COORDINATES, JUMPS, NEIGHBORS};
+    use crate::game::GameState;
+    use crate::hexgrid::{HexGrid, BOARD_SIZE, CELL_COUNT};
+
+    #[test]
+    fn cells_match_hex_grid() {
+        let grid = HexGrid::new_fill(BOARD_SIZE, ());
+        assert_eq!(grid.cells().len(), CELL_COUNT);
+        for index in 0..CELL_COUNT {
+            assert_eq!(COORDINATES[index], grid.coordinate(index));
+            assert_eq!(cell_index(COORDINATES[index]), Some(index));
+
+            let mask = |indices: &[usize]| indices.iter().fold(0, |mask, i| mask | 1 << i);
+            assert_eq!(NEIGHBORS[index], mask(grid.neighbors(index)));
+            assert_eq!(JUMPS[index], mask(grid.neighbors_at_distance_2(index)));
+            assert_eq!(NEIGHBORS[index] & !ALL_CELLS, 0);
+        }
+    }
+
+    #[test]
+    fn conversion_round_trip() {
+        let state = GameState::initialize();
+        let bitboard = BitboardState::from(&state);
+        assert_eq!(bitboard, BitboardState::initialize());
+        assert!(GameState::from(&bitboard) == state);
+        assert_eq!(bitboard.scores().rubies, 3);
+        assert_eq!(bitboard.scores().pearls, 3);
+        assert_eq!(bitboard.result(), None);
+    }
+}
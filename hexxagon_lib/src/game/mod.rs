@@ -1,10 +1,14 @@
 use core::fmt;
 
-use crate::hexgrid::{AxialVector, HexGrid};
+use smallvec::SmallVec;
 
+use crate::hexgrid::{AxialVector, HexGrid, BOARD_SIZE, CELL_COUNT};
+
+pub mod bitboard;
 pub mod rules;
+mod zobrist;
 
-#[derive(Clone, PartialEq, Copy, Debug)]
+#[derive(Clone, PartialEq, Copy, Debug, Hash)]
 pub enum Player {
     Rubies, // 0
     Pearls, // 1
@@ -17,9 +21,17 @@ impl Player {
             Player::Pearls => Player::Rubies,
         }
     }
+
+    /// Index of the player into per player arrays
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Player::Rubies => 0,
+            Player::Pearls => 1,
+        }
+    }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Hash)]
 pub enum CellState {
     Empty,
     Occupied(Player),
@@ -47,15 +59,131 @@ impl GameResult {
     }
 }
 
+#[derive(PartialEq, Debug)]
 pub struct Scores {
     pub rubies: i32,
     pub pearls: i32,
 }
 
+/// Piece counts of the field, updated on every change, so that scores and the end of the game
+/// are known without scanning the field
+#[derive(Clone, PartialEq, Debug)]
+struct PieceCounts {
+    /// Pieces of the Rubies and of the Pearls
+    pieces: [i32; 2],
+    /// Pieces of either player within distance 2 of every cell, which could move there. Stored
+    /// inline, so that cloning a state copies it without an allocation.
+    pieces_in_reach: [[i8; 2]; CELL_COUNT],
+    /// Empty cells, which each player can move to
+    reachable_empty: [i32; 2],
+}
+
+impl PieceCounts {
+    /// Full recomputation from the field
+    fn compute(field: &HexGrid<CellState>) -> PieceCounts {
+        let mut pieces = [0; 2];
+        debug_assert_eq!(field.cells().len(), CELL_COUNT, "Unexpected board size");
+        let mut pieces_in_reach = [[0; 2]; CELL_COUNT];
+        for (index, cell) in field.cells().iter().enumerate() {
+            if let CellState::Occupied(player) = cell {
+                pieces[player.index()] += 1;
+                for other in field
+                    .neighbors(index)
+                    .iter()
+                    .chain(field.neighbors_at_distance_2(index))
+                {
+                    pieces_in_reach[*other][player.index()] += 1;
+                }
+            }
+        }
+        let mut reachable_empty = [0; 2];
+        for (index, cell) in field.cells().iter().enumerate() {
+            if *cell == CellState::Empty {
+                for side in 0..2 {
+                    if pieces_in_reach[index][side] > 0 {
+                        reachable_empty[side] += 1;
+                    }
+                }
+            }
+        }
+        PieceCounts {
+            pieces,
+            pieces_in_reach,
+            reachable_empty,
+        }
+    }
+
+    /// Updates the counts for the cell at `index` changing from `old` to `new`. The other cells
+    /// of `field` must be up to date.
+    fn update(
+        &mut self,
+        field: &HexGrid<CellState>,
+        index: usize,
+        old: &CellState,
+        new: &CellState,
+    ) {
+        if *old == CellState::Empty {
+            self.update_reachable(index, -1);
+        }
+        // A capture removes a piece of one player and adds one of the other in a single pass
+        let mut change = [0; 2];
+        if let CellState::Occupied(player) = old {
+            change[player.index()] -= 1;
+        }
+        if let CellState::Occupied(player) = new {
+            change[player.index()] += 1;
+        }
+        if change != [0; 2] {
+            self.update_pieces(field, index, change);
+        }
+        if *new == CellState::Empty {
+            self.update_reachable(index, 1);
+        }
+    }
+
+    /// Adds or removes the empty cell at `index` from the reachable cells
+    fn update_reachable(&mut self, index: usize, change: i32) {
+        for side in 0..2 {
+            if self.pieces_in_reach[index][side] > 0 {
+                self.reachable_empty[side] += change;
+            }
+        }
+    }
+
+    /// Adds or removes pieces of either player at `index`
+    fn update_pieces(&mut self, field: &HexGrid<CellState>, index: usize, change: [i8; 2]) {
+        for (pieces, change) in self.pieces.iter_mut().zip(change) {
+            *pieces += change as i32;
+        }
+        for other in field
+            .neighbors(index)
+            .iter()
+            .chain(field.neighbors_at_distance_2(index))
+        {
+            let is_empty = *field.cell(*other) == CellState::Empty;
+            let in_reach = &mut self.pieces_in_reach[*other];
+            for ((in_reach, reachable_empty), change) in in_reach
+                .iter_mut()
+                .zip(&mut self.reachable_empty)
+                .zip(change)
+            {
+                let was_reachable = *in_reach > 0;
+                *in_reach += change;
+                if is_empty && was_reachable != (*in_reach > 0) {
+                    *reachable_empty += change as i32;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct GameState {
     next_move: Player,
     field: HexGrid<CellState>,
+    /// Zobrist hash of field and next player, updated on every change
+    hash: u64,
+    counts: PieceCounts,
 }
 
 impl fmt::Debug for GameState {
@@ -68,7 +196,7 @@ impl fmt::Debug for GameState {
 
 impl GameState {
     pub fn initialize() -> GameState {
-        let mut field = HexGrid::new_fill(5, CellState::Empty);
+        let mut field = HexGrid::new_fill(BOARD_SIZE, CellState::Empty);
         *field.get_mut((0, -1).into()).unwrap() = CellState::Blocked;
         *field.get_mut((1, 0).into()).unwrap() = CellState::Blocked;
         *field.get_mut((-1, 1).into()).unwrap() = CellState::Blocked;
@@ -81,10 +209,40 @@ impl GameState {
         *field.get_mut((-4, 4).into()).unwrap() = CellState::Occupied(Player::Pearls);
         *field.get_mut((4, 0).into()).unwrap() = CellState::Occupied(Player::Pearls);
 
-        GameState {
+        GameState::from_field(field, Player::Rubies)
+    }
+
+    /// State with the given field, whose hash and counts are computed from scratch
+    fn from_field(field: HexGrid<CellState>, next_move: Player) -> GameState {
+        let mut state = GameState {
+            counts: PieceCounts::compute(&field),
             field,
-            next_move: Player::Rubies,
-        }
+            next_move,
+            hash: 0,
+        };
+        state.hash = state.compute_hash();
+        state
+    }
+
+    fn compute_hash(&self) -> u64 {
+        self.field.cells().iter().enumerate().fold(
+            zobrist::next_player_key(self.next_move),
+            |hash, (index, cell)| hash ^ zobrist::cell_key(index, cell),
+        )
+    }
+
+    /// Key of this state, which is equal for equal states, and differs with high probability
+    /// otherwise
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn set_cell(&mut self, index: usize, value: CellState) {
+        self.counts
+            .update(&self.field, index, self.field.cell(index), &value);
+        let cell = self.field.cell_mut(index);
+        self.hash ^= zobrist::cell_key(index, cell) ^ zobrist::cell_key(index, &value);
+        *cell = value;
     }
 
     pub fn get_field(&self) -> &HexGrid<CellState> {
@@ -105,91 +263,64 @@ impl GameState {
         if move_length > 2 || move_length < 1 {
             return MoveResult::Fail;
         }
-        if !self.field.is_in_bounds(from) || !self.field.is_in_bounds(to) {
+        let (Some(from), Some(to)) = (self.field.index(from), self.field.index(to)) else {
             return MoveResult::Fail;
-        }
+        };
 
         // Test if source contains piece
-        if *self.field.get(from).unwrap() != CellState::Occupied(player) {
+        if *self.field.cell(from) != CellState::Occupied(player) {
             return MoveResult::Fail;
         }
 
         // Test if target is empty
-        if *self.field.get(to).unwrap() != CellState::Empty {
+        if *self.field.cell(to) != CellState::Empty {
             return MoveResult::Fail;
         }
         // Place piece at target
 
-        *self.field.get_mut(to).unwrap() = CellState::Occupied(player);
+        self.set_cell(to, CellState::Occupied(player));
 
         // Potentially remove piece at source
         if move_length == 2 {
-            *self.field.get_mut(from).unwrap() = CellState::Empty;
+            self.set_cell(from, CellState::Empty);
         }
 
         // Capture neighbors of target
-        for direction in 0..6 {
-            let to_capture = to + AxialVector::direction(direction);
-            // TODO unwrap fails after bounds check? at -1,4
-            if self.field.is_in_bounds(to_capture)
-                && *self.field.get(to_capture).unwrap() == CellState::Occupied(player.opponent())
-            {
-                *self.field.get_mut(to_capture).unwrap() = CellState::Occupied(player);
-            }
+        let captured: SmallVec<[usize; 6]> = self
+            .field
+            .neighbors(to)
+            .iter()
+            .copied()
+            .filter(|neighbor| {
+                *self.field.cell(*neighbor) == CellState::Occupied(player.opponent())
+            })
+            .collect();
+        for to_capture in captured {
+            self.set_cell(to_capture, CellState::Occupied(player));
         }
 
+        self.hash ^= zobrist::next_player_key(self.next_move)
+            ^ zobrist::next_player_key(self.next_move.opponent());
         self.next_move = self.next_move.opponent();
 
+        if cfg!(any(test, feature = "check-counts")) {
+            debug_assert_eq!(self.counts, PieceCounts::compute(&self.field));
+        }
+
         MoveResult::Success
     }
 
     pub fn scores(&self) -> Scores {
-        let mut rubies = 0;
-        let mut pearls = 0;
-
-        for (_pos, state) in self.field.tile_iter() {
-            match state {
-                CellState::Occupied(Player::Rubies) => {
-                    rubies += 1;
-                }
-                CellState::Occupied(Player::Pearls) => {
-                    pearls += 1;
-                }
-                _ => {}
-            }
+        Scores {
+            rubies: self.counts.pieces[Player::Rubies.index()],
+            pearls: self.counts.pieces[Player::Pearls.index()],
         }
-
-        Scores { rubies, pearls }
     }
 
+    /// The game ends once the player to move can not reach any empty cell
     pub fn result(&self) -> Option<GameResult> {
-        for (empty_cell_vec, tile) in self.field.tile_iter() {
-            if *tile == CellState::Empty {
-                let mut reachable = false;
-
-                'reachability_check: for ring_radius in 1..3 {
-                    let mut hex: AxialVector =
-                        ring_radius * AxialVector::direction(4) + *empty_cell_vec;
-                    for edge_direction in 0..6 {
-                        for _edge_index in 0..ring_radius {
-                            if self.field.is_in_bounds(hex)
-                                && *self.field.get(hex).unwrap()
-                                    == CellState::Occupied(self.next_move)
-                            {
-                                reachable = true;
-                                break 'reachability_check;
-                            }
-
-                            hex = hex + AxialVector::direction(edge_direction);
-                        }
-                    }
-                }
-
-                if reachable {
-                    // Game not finished yet: There is an empty cell reachable by the current player
-                    return None;
-                }
-            }
+        if self.counts.reachable_empty[self.next_move.index()] > 0 {
+            return None;
         }
         let scores = self.scores();
 
@@ -203,7 +334,9 @@ impl GameState {
 
 #[cfg(test)]
 mod tests {
-    use super::GameState;
+    use crate::ai::move_generation::sample_valid_move;
+
+    use super::{GameState, MoveResult, PieceCounts};
 
     #[test]
     fn initialize() {
@@ -213,4 +346,47 @@ mod tests {
         assert_eq!(score.rubies, 3);
         assert_eq!(state.result(), None);
     }
+
+    #[test]
+    fn incremental_hash() {
+        let mut state = GameState::initialize();
+        while state.result().is_none() {
+            let random_move = sample_valid_move(&state);
+            assert_eq!(
+                state.player_move(random_move.src, random_move.dst),
+                MoveResult::Success
+            );
+            assert_eq!(state.zobrist_hash(), state.compute_hash());
+        }
+    }
+
+    #[test]
+    fn incremental_counts() {
+        for _ in 0..10 {
+            let mut state = GameState::initialize();
+            while state.result().is_none() {
+                let random_move = sample_valid_move(&state);
+                state.player_move(random_move.src, random_move.dst);
+                assert_eq!(state.counts, PieceCounts::compute(&state.field));
+            }
+            assert_eq!(state.counts.reachable_empty[state.next_move.index()], 0);
+        }
+    }
+
+    #[test]
+    fn transposition_hash() {
+        let mut state = GameState::initialize();
+        state.player_move((-4, 0).into(), (-3, 0).into());
+        state.player_move((0, -4).into(), (0, -3).into());
+        state.player_move((0, 4).into(), (0, 3).into());
+
+        let mut transposed_state = GameState::initialize();
+        transposed_state.player_move((0, 4).into(), (0, 3).into());
+        transposed_state.player_move((0, -4).into(), (0, -3).into());
+        transposed_state.player_move((-4, 0).into(), (-3, 0).into());
+
+        assert!(state == transposed_state);
+        assert_eq!(state.zobrist_hash(), transposed_state.zobrist_hash());
+        assert_ne!(state.zobrist_hash(), GameState::initialize().zobrist_hash());
+    }
 }
@@ -7,6 +7,25 @@ use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards};
 
 use super::{GameResult, GameState, Player};
 
+impl From<Player> for PlayerIndex {
+    fn from(value: Player) -> Self {
+        match value {
+            Player::Rubies => PlayerIndex::ZERO,
+            Player::Pearls => PlayerIndex::ONE,
+        }
+    }
+}
+
+impl From<GameResult> for Rewards {
+    fn from(value: GameResult) -> Self {
+        match value {
+            GameResult::Tie => Rewards::new(&[0.5, 0.5]),
+            GameResult::Win(Player::Rubies) => Rewards::new(&[1.0, 0.0]),
+            GameResult::Win(Player::Pearls) => Rewards::new(&[0.0, 1.0]),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct HexxagonRules {}
 
@@ -14,7 +33,7 @@ impl GameRules for HexxagonRules {
     type State = GameState;
     type Action = HexxagonMove;
 
-    const N_PLAYERS: u32 = 2;
+    const N_PLAYERS: usize = 2;
 
     fn play(initial_state: &Self::State, action: &Self::Action) -> Self::State {
         let mut new_state = initial_state.clone();
@@ -22,14 +41,16 @@ impl GameRules for HexxagonRules {
         new_state
     }
 
-    fn random_rollout(initial_state: &Self::State) -> Rewards {
+    fn random_playout(initial_state: &Self::State) -> (Rewards, Vec<Self::Action>) {
         let mut state = initial_state.clone();
+        let mut moves = Vec::new();
         while !state.is_final() {
             let random_move = sample_valid_move(&state);
             state.player_move(random_move.src, random_move.dst);
+            moves.push(random_move);
         }
 
-        state.reward()
+        (state.reward(), moves)
     }
 }
 
@@ -43,28 +64,15 @@ impl GameStateTrait<HexxagonMove> for GameState {
     }
 
     fn reward(&self) -> Rewards {
-        match self.result() {
-            Some(GameResult::Tie) => Rewards {
-                player_0: 0.5,
-                player_1: 0.5,
-            },
-            Some(GameResult::Win(Player::Rubies)) => Rewards {
-                player_0: 1.0,
-                player_1: 0.0,
-            },
-            Some(GameResult::Win(Player::Pearls)) => Rewards {
-                player_0: 0.0,
-                player_1: 1.0,
-            },
-            None => panic!(),
-        }
+        Rewards::from(self.result().unwrap())
     }
 
     fn next_player(&self) -> PlayerIndex {
-        match self.next_player() {
-            Player::Rubies => PlayerIndex::Zero,
-            Player::Pearls => PlayerIndex::One,
-        }
+        PlayerIndex::from(self.next_player())
+    }
+
+    fn hash_key(&self) -> Option<u64> {
+        Some(self.zobrist_hash())
     }
 }
 
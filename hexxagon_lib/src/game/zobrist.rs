@@ -0,0 +1,53 @@
+//! Zobrist hashing: The key of a state is the XOR of one random key per occupied or blocked cell,
+//! so it can be updated incrementally whenever a single cell changes.
+
+use crate::hexgrid::CELL_COUNT;
+
+use super::{CellState, Player};
+
+/// Keys of the non-empty cell states (Rubies, Pearls, Blocked) for every cell, by its index in
+/// [HexGrid](crate::hexgrid::HexGrid)
+static CELL_KEYS: [[u64; 3]; CELL_COUNT] = generate_cell_keys();
+static PEARLS_TO_MOVE_KEY: u64 = splitmix64(0).1;
+
+/// Pseudo random number generator, returns the next state and the output
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_cell_keys() -> [[u64; 3]; CELL_COUNT] {
+    let mut keys = [[0; 3]; CELL_COUNT];
+    let mut state = splitmix64(0).0;
+    let mut cell = 0;
+    while cell < CELL_COUNT {
+        let mut cell_state = 0;
+        while cell_state < 3 {
+            let (next_state, key) = splitmix64(state);
+            state = next_state;
+            keys[cell][cell_state] = key;
+            cell_state += 1;
+        }
+        cell += 1;
+    }
+    keys
+}
+
+pub(super) fn cell_key(index: usize, cell: &CellState) -> u64 {
+    match cell {
+        CellState::Empty => 0,
+        CellState::Occupied(Player::Rubies) => CELL_KEYS[index][0],
+        CellState::Occupied(Player::Pearls) => CELL_KEYS[index][1],
+        CellState::Blocked => CELL_KEYS[index][2],
+    }
+}
+
+pub(super) fn next_player_key(player: Player) -> u64 {
+    match player {
+        Player::Rubies => 0,
+        Player::Pearls => PEARLS_TO_MOVE_KEY,
+    }
+}
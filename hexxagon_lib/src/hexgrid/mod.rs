@@ -1,25 +1,108 @@
-use rustc_hash::FxHashMap;
+use std::sync::Arc;
 
 pub trait CellTypeTrait: Clone {}
 impl<T: Clone> CellTypeTrait for T {}
 
-#[derive(Clone, PartialEq)]
+/// Size of the Hexxagon board, as passed to [HexGrid::new_fill]
+pub const BOARD_SIZE: i32 = 5;
+/// Number of cells of the Hexxagon board
+pub const CELL_COUNT: usize = cell_count(BOARD_SIZE);
+
+/// Number of cells of a grid of the given size
+pub const fn cell_count(size: i32) -> usize {
+    (1 + 3 * size * (size - 1)) as usize
+}
+
+/// Cell indices and neighbor tables of a grid, which all grids of the same size share
+#[derive(Debug)]
+struct GridLayout {
+    /// Largest distance of a cell from the center
+    radius: i32,
+    /// Index of the first cell of every row, from `r = -radius` to `r = radius`
+    row_starts: Vec<usize>,
+    coordinates: Vec<AxialVector>,
+    /// Indices of the cells at distance one of every cell
+    neighbors: Vec<Vec<usize>>,
+    /// Indices of the cells at distance two of every cell
+    neighbors_at_distance_2: Vec<Vec<usize>>,
+}
+
+impl GridLayout {
+    fn new(size: i32) -> GridLayout {
+        let radius = size - 1;
+        let mut row_starts = Vec::new();
+        let mut coordinates = Vec::new();
+        for r in -radius..=radius {
+            row_starts.push(coordinates.len());
+            for q in Self::row_q_range(radius, r) {
+                coordinates.push(AxialVector::new(q, r));
+            }
+        }
+
+        let mut layout = GridLayout {
+            radius,
+            row_starts,
+            coordinates,
+            neighbors: Vec::new(),
+            neighbors_at_distance_2: Vec::new(),
+        };
+        let ring = |center: AxialVector, ring_radius: i32| {
+            AxialVector::ring(center, ring_radius)
+                .filter_map(|coordinate| layout.index(coordinate))
+                .collect::<Vec<_>>()
+        };
+        let neighbors = layout.coordinates.iter().map(|c| ring(*c, 1)).collect();
+        let neighbors_at_distance_2 = layout.coordinates.iter().map(|c| ring(*c, 2)).collect();
+        layout.neighbors = neighbors;
+        layout.neighbors_at_distance_2 = neighbors_at_distance_2;
+        layout
+    }
+
+    /// Coordinates `q` of the cells in row `r`
+    fn row_q_range(radius: i32, r: i32) -> std::ops::RangeInclusive<i32> {
+        (-radius).max(-radius - r)..=radius.min(radius - r)
+    }
+
+    fn index(&self, coordinate: AxialVector) -> Option<usize> {
+        if coordinate.length() > self.radius {
+            return None;
+        }
+        let row = (coordinate.r + self.radius) as usize;
+        let row_q_start = *Self::row_q_range(self.radius, coordinate.r).start();
+        Some(self.row_starts[row] + (coordinate.q - row_q_start) as usize)
+    }
+}
+
+/// Hexagonal grid, whose cells are stored row by row. Cells can be accessed by their axial
+/// coordinate, or faster by their index.
+#[derive(Clone)]
 pub struct HexGrid<CellType: CellTypeTrait> {
     size: i32,
-    storage: FxHashMap<AxialVector, CellType>,
+    layout: Arc<GridLayout>,
+    cells: Vec<CellType>,
+}
+
+impl<CellType: CellTypeTrait + PartialEq> PartialEq for HexGrid<CellType> {
+    fn eq(&self, other: &Self) -> bool {
+        // The layout follows from the size
+        self.size == other.size && self.cells == other.cells
+    }
 }
 
 impl<CellType: CellTypeTrait> HexGrid<CellType> {
     pub fn get_mut(&mut self, coordinate: AxialVector) -> Option<&mut CellType> {
-        self.storage.get_mut(&coordinate)
+        let index = self.index(coordinate)?;
+        Some(&mut self.cells[index])
     }
 
     pub fn get(&self, coordinate: AxialVector) -> Option<&CellType> {
-        self.storage.get(&coordinate)
+        let index = self.index(coordinate)?;
+        Some(&self.cells[index])
     }
 
+    /// All cells with their coordinates, row by row from top left to bottom right
     pub fn tile_iter(&self) -> impl Iterator<Item = (&AxialVector, &CellType)> {
-        self.storage.iter()
+        self.layout.coordinates.iter().zip(&self.cells)
     }
 
     /// Creates a new HexGrid of specified size, with all cells set to specified value.
@@ -29,32 +112,52 @@ impl<CellType: CellTypeTrait> HexGrid<CellType> {
     /// * `size` - Radius of circumcircle
     /// * `value` - The initial value of every cell
     pub fn new_fill(size: i32, value: CellType) -> HexGrid<CellType> {
-        // TODO: nice storage...
-        // r goes from -size to +size, number of rows is 2*size+1
-        // Row index "r", a row is from top left to bottom right
-        // Row length is (2*size+1)-abs(r)
-        // Row starts with item q=max(-size,-size-r)
-
-        let mut map = FxHashMap::default();
-        map.insert(AxialVector::new(0, 0), value.clone());
-        for ring_radius in 1..size {
-            let mut hex: AxialVector = ring_radius * AxialVector::direction(4);
-            for edge_direction in 0..6 {
-                for _edge_index in 0..ring_radius {
-                    map.insert(hex, value.clone());
-                    hex = hex + AxialVector::direction(edge_direction);
-                }
-            }
-        }
+        let layout = GridLayout::new(size);
+        assert_eq!(layout.coordinates.len(), cell_count(size));
 
-        assert_eq!(map.len() as i32, 1 + 3 * size * (size - 1));
-
-        HexGrid { size, storage: map }
+        HexGrid {
+            size,
+            cells: vec![value; layout.coordinates.len()],
+            layout: Arc::new(layout),
+        }
     }
 
     pub fn is_in_bounds(&self, coordinate: AxialVector) -> bool {
         coordinate.length() < self.size
     }
+
+    /// Index of the cell at `coordinate`, if it is in bounds
+    pub fn index(&self, coordinate: AxialVector) -> Option<usize> {
+        self.layout.index(coordinate)
+    }
+
+    /// Coordinate of the cell with the given index
+    pub fn coordinate(&self, index: usize) -> AxialVector {
+        self.layout.coordinates[index]
+    }
+
+    /// All cells, ordered by index
+    pub fn cells(&self) -> &[CellType] {
+        &self.cells
+    }
+
+    pub fn cell(&self, index: usize) -> &CellType {
+        &self.cells[index]
+    }
+
+    pub fn cell_mut(&mut self, index: usize) -> &mut CellType {
+        &mut self.cells[index]
+    }
+
+    /// Indices of the cells next to the cell with the given index
+    pub fn neighbors(&self, index: usize) -> &[usize] {
+        &self.layout.neighbors[index]
+    }
+
+    /// Indices of the cells at distance two from the cell with the given index
+    pub fn neighbors_at_distance_2(&self, index: usize) -> &[usize] {
+        &self.layout.neighbors_at_distance_2[index]
+    }
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -98,6 +201,21 @@ impl AxialVector {
         (self.q.abs() + (self.q + self.r).abs() + self.r.abs()).abs() / 2
     }
 
+    /// Coordinates at distance `radius` from `center`, walking around the ring from the corner in
+    /// direction 4
+    pub fn ring(center: AxialVector, radius: i32) -> impl Iterator<Item = AxialVector> {
+        (0..6).flat_map(move |edge_direction| {
+            let edge_start = center
+                + radius * AxialVector::direction(4)
+                + (0..edge_direction).fold(AxialVector::new(0, 0), |offset, direction| {
+                    offset + radius * AxialVector::direction(direction)
+                });
+            (0..radius).map(move |edge_index| {
+                edge_start + edge_index * AxialVector::direction(edge_direction)
+            })
+        })
+    }
+
     pub fn direction(index: u8) -> AxialVector {
         assert!(index < 6);
         match index {
@@ -211,4 +329,42 @@ mod tests {
         assert!(map2.get((-1, 4).into()).is_some());
         assert!(!map2.is_in_bounds((-2, 5).into()));
     }
+
+    #[test]
+    fn indices_are_row_major() {
+        let map = HexGrid::new_fill(5, 0u8);
+        assert_eq!(map.cells().len(), 61);
+        for (index, (coordinate, _)) in map.tile_iter().enumerate() {
+            assert_eq!(map.index(*coordinate), Some(index));
+            assert_eq!(map.coordinate(index), *coordinate);
+        }
+        let coordinates = map
+            .tile_iter()
+            .map(|(c, _)| (c.r(), c.q()))
+            .collect::<Vec<_>>();
+        assert!(coordinates.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(map.coordinate(0), AxialVector::new(0, -4));
+        assert_eq!(map.index((-1, 5).into()), None);
+    }
+
+    #[test]
+    fn neighbor_tables() {
+        let map = HexGrid::new_fill(5, 0u8);
+        let center = map.index((0, 0).into()).unwrap();
+        assert_eq!(map.neighbors(center).len(), 6);
+        assert_eq!(map.neighbors_at_distance_2(center).len(), 12);
+        let corner = map.index((4, -4).into()).unwrap();
+        assert_eq!(map.neighbors(corner).len(), 3);
+        assert_eq!(map.neighbors_at_distance_2(corner).len(), 5);
+
+        for index in 0..map.cells().len() {
+            let distance =
+                |other: &usize| (map.coordinate(*other) - map.coordinate(index)).length();
+            assert!(map.neighbors(index).iter().all(|n| distance(n) == 1));
+            assert!(map
+                .neighbors_at_distance_2(index)
+                .iter()
+                .all(|n| distance(n) == 2));
+        }
+    }
 }
@@ -0,0 +1,98 @@
+use game_ai::{GameRules, GameStateTrait};
+use hexxagon_lib::ai::move_generation::{all_moves, sample_valid_move};
+use hexxagon_lib::ai::HexxagonMove;
+use hexxagon_lib::game::bitboard::{BitboardRules, BitboardState};
+use hexxagon_lib::game::rules::HexxagonRules;
+use hexxagon_lib::game::GameState;
+use hexxagon_lib::hexgrid::AxialVector;
+use rand::Rng;
+
+/// Moves of the quick differential test, which runs with every `cargo test`
+const MOVES: usize = 200_000;
+/// Moves of the full differential test, for release builds: `cargo test --release -- --ignored`
+const FULL_MOVES: usize = 2_000_000;
+
+/// Random pair of cells, which may be off the board or far apart
+fn random_move(rng: &mut impl Rng) -> HexxagonMove {
+    let mut cell = || AxialVector::new(rng.gen_range(-5..=5), rng.gen_range(-5..=5));
+    HexxagonMove {
+        src: cell(),
+        dst: cell(),
+    }
+}
+
+fn assert_same(state: &GameState, bitboard: &BitboardState) {
+    assert!(GameState::from(bitboard) == *state);
+    assert!(BitboardState::from(state) == *bitboard);
+    assert_eq!(bitboard.zobrist_hash(), state.zobrist_hash());
+    assert_eq!(bitboard.next_player(), state.next_player());
+    assert_eq!(bitboard.scores(), state.scores());
+    assert_eq!(bitboard.result(), state.result());
+    assert_eq!(bitboard.is_final(), state.is_final());
+    assert_eq!(
+        GameStateTrait::next_player(bitboard),
+        GameStateTrait::next_player(state)
+    );
+    if !state.is_final() {
+        let mut moves = all_moves(state);
+        let mut bitboard_moves = bitboard.all_moves();
+        let key = |m: &HexxagonMove| (m.src.r(), m.src.q(), m.dst.r(), m.dst.q());
+        moves.sort_by_key(key);
+        bitboard_moves.sort_by_key(key);
+        assert_eq!(bitboard_moves, moves);
+    }
+}
+
+/// Plays the same moves on both representations and compares the positions
+fn play_differential(moves: usize) {
+    let mut rng = rand::thread_rng();
+    let mut state = GameState::initialize();
+    let mut bitboard = BitboardState::initialize();
+    for played in 0..moves {
+        // Mostly legal moves, from both move generators, and some arbitrary ones
+        let next_move = match rng.gen_range(0..4) {
+            0 => random_move(&mut rng),
+            1 => bitboard.sample_valid_move(),
+            _ => sample_valid_move(&state),
+        };
+        assert_eq!(
+            bitboard.player_move(next_move.src, next_move.dst),
+            state.player_move(next_move.src, next_move.dst)
+        );
+        // Comparing every position in full would dominate the run time
+        if played % 16 == 0 || state.is_final() {
+            assert_same(&state, &bitboard);
+        } else {
+            assert_eq!(bitboard.zobrist_hash(), state.zobrist_hash());
+            assert_eq!(bitboard.is_final(), state.is_final());
+        }
+        if state.is_final() {
+            assert_eq!(bitboard.reward(), state.reward());
+            state = GameState::initialize();
+            bitboard = BitboardState::initialize();
+        }
+    }
+}
+
+#[test]
+fn bitboard_matches_game_state() {
+    play_differential(MOVES);
+}
+
+#[test]
+#[ignore = "plays millions of moves, run in release builds"]
+fn bitboard_matches_game_state_in_millions_of_moves() {
+    play_differential(FULL_MOVES);
+}
+
+#[test]
+fn playouts_end_in_final_states() {
+    for _ in 0..100 {
+        let (rewards, moves) = BitboardRules::random_playout(&BitboardState::initialize());
+        let replayed = moves.iter().fold(GameState::initialize(), |state, action| {
+            HexxagonRules::play(&state, action)
+        });
+        assert!(replayed.is_final());
+        assert_eq!(replayed.reward(), rewards);
+    }
+}
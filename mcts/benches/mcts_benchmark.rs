@@ -1,32 +1,125 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 use game_ai::{GameAi, GameRules};
+use hexxagon_lib::ai::{CaptureHeuristic, HexxagonEvaluator};
 use hexxagon_lib::game::rules::HexxagonRules;
-use mcts::GenericMonteCarloTreeSearchAi;
+use mcts::{
+    GenericMonteCarloTreeSearchAi, HeuristicRollout, Parallelism, RaveSchedule, TruncatedRollout,
+    UniformRollout,
+};
 use tic_tac_toe::TTTRules;
 
+const ITERATIONS: usize = 100;
+
 fn tic_tac_toe_search(c: &mut Criterion) {
     let initial_state = <TTTRules as GameRules>::State::default();
-    c.bench_function("ttt_100_iterations", |b| {
+    let mut group = c.benchmark_group("ttt");
+    // Reported as playouts per second
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    group.bench_function("ttt_100_iterations", |b| {
         b.iter(|| {
             let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(
-                mcts::StopCondition::Iterations(100),
+                mcts::StopCondition::Iterations(ITERATIONS),
             );
             ai.determine_next_move(&initial_state)
         })
     });
+    group.finish();
 }
 
 fn hexxagon_search(c: &mut Criterion) {
     let initial_state = <HexxagonRules as GameRules>::State::default();
-    c.bench_function("hexxagon_100_iterations", |b| {
+    let mut group = c.benchmark_group("hexxagon");
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    group.bench_function("hexxagon_100_iterations", |b| {
         b.iter(|| {
             let mut ai = GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(
-                mcts::StopCondition::Iterations(100),
+                mcts::StopCondition::Iterations(ITERATIONS),
             );
             ai.determine_next_move(&initial_state)
         })
     });
+    group.bench_function("hexxagon_100_iterations_rave", |b| {
+        b.iter(|| {
+            let mut ai = GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(
+                mcts::StopCondition::Iterations(ITERATIONS),
+            )
+            .with_rave(RaveSchedule::Equivalence { k: 1000.0 });
+            ai.determine_next_move(&initial_state)
+        })
+    });
+    group.finish();
+}
+
+/// Playouts per second of the parallel modes, each with four workers
+fn hexxagon_parallel_search(c: &mut Criterion) {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+    let mut group = c.benchmark_group("hexxagon_parallel");
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    let configurations = [
+        ("sequential", Parallelism::Sequential),
+        ("shared_tree_4", Parallelism::SharedTree { threads: 4 }),
+        ("root_4", Parallelism::Root { trees: 4 }),
+        ("leaf_4", Parallelism::Leaf { rollouts: 4 }),
+    ];
+    for (name, parallelism) in configurations {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut ai = GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(
+                    mcts::StopCondition::Iterations(ITERATIONS),
+                )
+                .with_parallelism(parallelism.clone());
+                ai.determine_next_move(&initial_state)
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Playouts per second with full random rollouts, and with rollouts truncated after 10 plies
+fn hexxagon_rollout_search(c: &mut Criterion) {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+    let mut group = c.benchmark_group("hexxagon_rollout");
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    let create_ai = || {
+        GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(mcts::StopCondition::Iterations(
+            ITERATIONS,
+        ))
+    };
+    group.bench_function("uniform", |b| {
+        b.iter(|| create_ai().determine_next_move(&initial_state))
+    });
+    group.bench_function("truncated_uniform_10", |b| {
+        b.iter(|| {
+            create_ai()
+                .with_rollout_policy(TruncatedRollout::new(
+                    UniformRollout::default(),
+                    HexxagonEvaluator::default(),
+                    10,
+                    3.0,
+                ))
+                .determine_next_move(&initial_state)
+        })
+    });
+    group.bench_function("truncated_heuristic_10", |b| {
+        b.iter(|| {
+            create_ai()
+                .with_rollout_policy(TruncatedRollout::new(
+                    HeuristicRollout::new(CaptureHeuristic {}, 1.0),
+                    HexxagonEvaluator::default(),
+                    10,
+                    3.0,
+                ))
+                .determine_next_move(&initial_state)
+        })
+    });
+    group.finish();
 }
 
-criterion_group!(benches, tic_tac_toe_search, hexxagon_search);
+criterion_group!(
+    benches,
+    tic_tac_toe_search,
+    hexxagon_search,
+    hexxagon_parallel_search,
+    hexxagon_rollout_search
+);
 criterion_main!(benches);
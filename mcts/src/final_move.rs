@@ -0,0 +1,249 @@
+//! Choice of the move to play once the search is finished
+
+use game_ai::{GameRules, GameStateTrait, StopCondition};
+use itertools::Itertools;
+use rand::distributions::{Distribution, WeightedIndex};
+
+use crate::solver;
+use crate::tree::{Edge, NodeId, Tree};
+
+/// Additional searches for `FinalMovePolicy::MaxRobustChild`, before falling back to the robust
+/// child. Each gets a hundredth of the budget, which the first search leaves over for them.
+pub(crate) const MAX_ROBUST_BATCHES: u32 = 10;
+const MAX_ROBUST_BATCH_SHARE: u32 = 100;
+
+/// Which child of the root is played after the search
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FinalMovePolicy {
+    /// Child with the most playouts
+    #[default]
+    RobustChild,
+    /// Child with the highest mean reward
+    MaxChild,
+    /// Child with both the most playouts and the highest mean reward. A tenth of the budget is
+    /// kept back to continue the search while there is none, then it falls back to the robust
+    /// child.
+    MaxRobustChild,
+    /// Child with the highest lower confidence bound `mean reward - a / sqrt(playouts)`
+    SecureChild { a: f32 },
+    /// Random child with probability proportional to `playouts^(1 / temperature)`, which varies
+    /// the moves of self-play games. Temperature zero is the robust child.
+    Sample { temperature: f32 },
+}
+
+/// Stop conditions of the first search for `FinalMovePolicy::MaxRobustChild`, and of each
+/// additional search, which together stay within `stop_condition`
+pub(crate) fn split_max_robust_budget(
+    stop_condition: &StopCondition,
+) -> (StopCondition, StopCondition) {
+    match stop_condition {
+        StopCondition::Iterations(iterations) => {
+            let batch = iterations / MAX_ROBUST_BATCH_SHARE as usize;
+            (
+                StopCondition::Iterations(iterations - MAX_ROBUST_BATCHES as usize * batch),
+                StopCondition::Iterations(batch),
+            )
+        }
+        StopCondition::Time(duration) => {
+            let batch = *duration / MAX_ROBUST_BATCH_SHARE;
+            (
+                StopCondition::Time(*duration - MAX_ROBUST_BATCHES * batch),
+                StopCondition::Time(batch),
+            )
+        }
+    }
+}
+
+/// Whether the child behind `edge` is a proven loss for the player to move at `id`
+fn is_proven_loss<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+    edge: &Edge<Rules::Action>,
+) -> bool {
+    let player = tree.node(id).state.next_player();
+    tree.node(edge.child.unwrap())
+        .proven
+        .get()
+        .is_some_and(|value| solver::is_loss(value, &player))
+}
+
+/// Playouts and mean reward for the player to move at `id` of every expanded child. Proven
+/// losses are left out, unless all children are.
+fn child_statistics<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+) -> impl Iterator<Item = (&Edge<Rules::Action>, f32, f32)> {
+    let player = tree.node(id).state.next_player();
+    let children = tree.children(id);
+    let all_lost = children.iter().all(|edge| is_proven_loss(tree, id, edge));
+    children
+        .iter()
+        .filter(move |edge| all_lost || !is_proven_loss(tree, id, edge))
+        .map(move |edge| {
+            let stats = &tree.node(edge.child.unwrap()).stats;
+            let playouts = stats.playouts();
+            (edge, playouts, stats.reward(&player) / playouts)
+        })
+}
+
+/// Child which has both the most playouts and the highest mean reward, if there is one
+pub(crate) fn max_robust_child<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+) -> Option<&Edge<Rules::Action>> {
+    let children = child_statistics(tree, id).collect_vec();
+    let max_playouts = children.iter().map(|child| child.1).fold(0.0, f32::max);
+    let max_mean = children
+        .iter()
+        .map(|child| child.2)
+        .fold(f32::NEG_INFINITY, f32::max);
+    children
+        .into_iter()
+        .find(|(_, playouts, mean)| *playouts == max_playouts && *mean == max_mean)
+        .map(|(edge, _, _)| edge)
+}
+
+/// Selects the edge to the child, whose action is played. A proven win is chosen regardless of
+/// the policy.
+pub(crate) fn select_final_move<'tree, Rules: GameRules>(
+    tree: &'tree Tree<Rules>,
+    id: NodeId,
+    policy: &FinalMovePolicy,
+) -> &'tree Edge<Rules::Action> {
+    assert!(!tree.children(id).is_empty());
+    let player = tree.node(id).state.next_player();
+    let proven_win = tree.children(id).iter().find(|edge| {
+        tree.node(edge.child.unwrap())
+            .proven
+            .get()
+            .is_some_and(|value| solver::is_win(value, &player))
+    });
+    if let Some(edge) = proven_win {
+        return edge;
+    }
+    let children = child_statistics(tree, id);
+    let best = match policy {
+        FinalMovePolicy::RobustChild => {
+            // Mean reward breaks ties
+            children.max_by(|(_, playouts_1, mean_1), (_, playouts_2, mean_2)| {
+                playouts_1
+                    .total_cmp(playouts_2)
+                    .then(mean_1.total_cmp(mean_2))
+            })
+        }
+        FinalMovePolicy::MaxChild => {
+            children.max_by(|(_, _, mean_1), (_, _, mean_2)| mean_1.total_cmp(mean_2))
+        }
+        FinalMovePolicy::MaxRobustChild => {
+            return max_robust_child(tree, id)
+                .unwrap_or_else(|| select_final_move(tree, id, &FinalMovePolicy::RobustChild));
+        }
+        FinalMovePolicy::Sample { temperature } if *temperature <= 0.0 => {
+            return select_final_move(tree, id, &FinalMovePolicy::RobustChild);
+        }
+        FinalMovePolicy::Sample { temperature } => {
+            let children = children.collect_vec();
+            let weights = children
+                .iter()
+                .map(|(_, playouts, _)| playouts.powf(1.0 / temperature));
+            match WeightedIndex::new(weights) {
+                Ok(distribution) => Some(children[distribution.sample(&mut rand::thread_rng())]),
+                // The weights overflow at very low temperatures
+                Err(_) => return select_final_move(tree, id, &FinalMovePolicy::RobustChild),
+            }
+        }
+        FinalMovePolicy::SecureChild { a } => {
+            let lower_bound = |playouts: f32, mean: f32| mean - a / playouts.sqrt();
+            children.max_by(|(_, playouts_1, mean_1), (_, playouts_2, mean_2)| {
+                lower_bound(*playouts_1, *mean_1).total_cmp(&lower_bound(*playouts_2, *mean_2))
+            })
+        }
+    };
+    best.unwrap().0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use game_ai::{Rewards, StopCondition};
+    use tic_tac_toe::{TTTRules, TTTState};
+
+    use super::{max_robust_child, select_final_move, split_max_robust_budget, FinalMovePolicy};
+    use crate::tree::{NodeId, Tree, ROOT};
+
+    /// Root with a child of 10 playouts and mean reward 0.5, and one of 3 playouts and mean 1
+    fn tree() -> (Tree<TTTRules>, NodeId, NodeId) {
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        let often_visited = tree.expand_random_child(ROOT);
+        for playout in 0..10 {
+            let reward = (playout % 2) as f32;
+            tree.backup(often_visited, &Rewards::new(&[reward, 1.0 - reward]));
+        }
+        let promising = tree.expand_random_child(ROOT);
+        for _ in 0..3 {
+            tree.backup(promising, &Rewards::new(&[1.0, 0.0]));
+        }
+        (tree, often_visited, promising)
+    }
+
+    fn chosen_child(tree: &Tree<TTTRules>, policy: FinalMovePolicy) -> NodeId {
+        select_final_move(tree, ROOT, &policy).child.unwrap()
+    }
+
+    #[test]
+    fn policies_choose_expected_child() {
+        let (tree, often_visited, promising) = tree();
+        assert_eq!(
+            chosen_child(&tree, FinalMovePolicy::RobustChild),
+            often_visited
+        );
+        assert_eq!(chosen_child(&tree, FinalMovePolicy::MaxChild), promising);
+        assert_eq!(
+            chosen_child(&tree, FinalMovePolicy::SecureChild { a: 0.1 }),
+            promising
+        );
+        assert_eq!(
+            chosen_child(&tree, FinalMovePolicy::SecureChild { a: 2.0 }),
+            often_visited
+        );
+
+        // No child is best in both, so max-robust falls back to the robust child
+        assert!(max_robust_child(&tree, ROOT).is_none());
+        assert_eq!(
+            chosen_child(&tree, FinalMovePolicy::MaxRobustChild),
+            often_visited
+        );
+    }
+
+    #[test]
+    fn sampling_follows_playouts() {
+        let (tree, often_visited, promising) = tree();
+        assert_eq!(
+            chosen_child(&tree, FinalMovePolicy::Sample { temperature: 0.0 }),
+            often_visited
+        );
+        // Probabilities 10/13 and 3/13
+        let samples = (0..1000)
+            .map(|_| chosen_child(&tree, FinalMovePolicy::Sample { temperature: 1.0 }))
+            .collect::<Vec<_>>();
+        let promising_samples = samples.iter().filter(|child| **child == promising).count();
+        assert!((100..400).contains(&promising_samples));
+    }
+
+    #[test]
+    fn max_robust_searches_stay_within_budget() {
+        let (main_search, batch) = split_max_robust_budget(&StopCondition::Iterations(1050));
+        assert!(matches!(main_search, StopCondition::Iterations(950)));
+        assert!(matches!(batch, StopCondition::Iterations(10)));
+
+        let (main_search, batch) =
+            split_max_robust_budget(&StopCondition::Time(Duration::from_secs(1)));
+        assert!(
+            matches!(main_search, StopCondition::Time(duration) if duration == Duration::from_millis(900))
+        );
+        assert!(
+            matches!(batch, StopCondition::Time(duration) if duration == Duration::from_millis(10))
+        );
+    }
+}
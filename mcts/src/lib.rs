@@ -1,3 +1,22 @@
+mod final_move;
 mod mcts_generic;
+mod model;
+mod parallel;
+mod prior;
+mod rave;
+mod rollout;
+mod solver;
+mod tree;
+mod tree_policy;
+pub use final_move::FinalMovePolicy;
+pub use game_ai::StopCondition;
 pub use mcts_generic::GenericMonteCarloTreeSearchAi;
-pub use mcts_generic::StopCondition;
+pub use model::DirichletNoise;
+pub use parallel::Parallelism;
+pub use prior::{HeuristicPrior, MovePrior, ProgressiveWidening};
+pub use rave::RaveSchedule;
+pub use rollout::{
+    EpsilonGreedyRollout, HeuristicRollout, Playout, RolloutPolicy, TruncatedRollout,
+    UniformRollout,
+};
+pub use tree_policy::{ChildStatistics, Puct, ThompsonSampling, TreePolicy, Ucb1, Ucb1Tuned};
@@ -1,250 +1,684 @@
-use std::cell::RefCell;
-use std::rc::{Rc, Weak};
-
 use graphviz_rust::dot_structures::Graph;
 use rand::seq::SliceRandom;
 
-use game_ai::{GameAi, GameRules, GameStateTrait, Rewards};
+use game_ai::{
+    GameAi, GameRules, GameStateTrait, PlayerIndex, PolicyValueModel, Pondering, Rewards,
+    SearchControl, SearchProgress, StopCondition,
+};
 
 use graphviz_rust::dot_generator::*;
 use graphviz_rust::dot_structures::*;
-use rustc_hash::FxHashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use itertools::Itertools;
 
+use crate::final_move::{self, FinalMovePolicy};
+use crate::model::{DirichletNoise, ModelEvaluation, ModelPrior};
+use crate::parallel::{self, Parallelism};
+use crate::prior::{MovePrior, ProgressiveWidening, SharedMovePrior};
+use crate::rave::{self, RaveSchedule};
+use crate::rollout::{RolloutPolicy, SharedRolloutPolicy, UniformRollout};
+use crate::solver;
+use crate::tree::{self, NodeId, Tree, ROOT};
+use crate::tree_policy::{ChildStatistics, Puct, TreePolicy, Ucb1};
+
+/// Size of the tree at which pondering stops, which bounds its memory while the opponent thinks
+/// for a long time
+const MAX_PONDER_TREE_SIZE: usize = 1 << 21;
+/// Time between two progress reports of a controlled search
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Configuration of the search, which all threads share
+pub(crate) struct Settings<Rules: GameRules> {
+    pub(crate) stop_condition: StopCondition,
+    pub(crate) tree_policy: Arc<dyn TreePolicy>,
+    pub(crate) rollout_policy: SharedRolloutPolicy<Rules>,
+    pub(crate) tree_reuse: bool,
+    pub(crate) parallelism: Parallelism,
+    pub(crate) final_move_policy: FinalMovePolicy,
+    pub(crate) solver: bool,
+    pub(crate) rave: Option<RaveSchedule>,
+    pub(crate) move_prior: Option<SharedMovePrior<Rules>>,
+    pub(crate) progressive_widening: Option<ProgressiveWidening>,
+    pub(crate) progressive_bias: f32,
+    pub(crate) root_noise: Option<DirichletNoise>,
+    pub(crate) first_play_urgency: Option<f32>,
+    /// Control of the current search, if the caller can stop it
+    pub(crate) control: Option<SearchControl<Rules::Action>>,
+}
+
+impl<Rules: GameRules> Clone for Settings<Rules> {
+    fn clone(&self) -> Self {
+        Settings {
+            stop_condition: self.stop_condition.clone(),
+            tree_policy: self.tree_policy.clone(),
+            rollout_policy: self.rollout_policy.clone(),
+            tree_reuse: self.tree_reuse,
+            parallelism: self.parallelism.clone(),
+            final_move_policy: self.final_move_policy.clone(),
+            solver: self.solver,
+            rave: self.rave.clone(),
+            move_prior: self.move_prior.clone(),
+            progressive_widening: self.progressive_widening.clone(),
+            progressive_bias: self.progressive_bias,
+            root_noise: self.root_noise.clone(),
+            first_play_urgency: self.first_play_urgency,
+            control: self.control.clone(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct GenericMonteCarloTreeSearchAi<Rules: GameRules> {
-    stop_condition: StopCondition,
-    last_tree: Rc<RefCell<Tree<Rules>>>,
-    next_id: i32,
-    c: f32,
+    settings: Settings<Rules>,
+    last_tree: Tree<Rules>,
+    /// Whether the root noise was mixed into the priors of the root of the last tree
+    root_noise_added: bool,
+    pondering: Pondering<Tree<Rules>>,
 }
 
-impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
+impl<Rules: GameRules + 'static> GenericMonteCarloTreeSearchAi<Rules> {
     pub fn new(stop_condition: StopCondition) -> GenericMonteCarloTreeSearchAi<Rules> {
         GenericMonteCarloTreeSearchAi {
-            stop_condition,
+            settings: Settings {
+                stop_condition,
+                tree_policy: Arc::new(Ucb1::default()),
+                rollout_policy: Arc::new(UniformRollout::<Rules>::default()),
+                tree_reuse: true,
+                parallelism: Parallelism::Sequential,
+                final_move_policy: FinalMovePolicy::default(),
+                solver: false,
+                rave: None,
+                move_prior: None,
+                progressive_widening: None,
+                progressive_bias: 0.0,
+                root_noise: None,
+                first_play_urgency: None,
+                control: None,
+            },
             last_tree: Default::default(),
-            next_id: 1,
-            c: 2.0f32.sqrt(),
+            root_noise_added: false,
+            pondering: Default::default(),
         }
     }
 
+    /// Search with UCB1 and exploration constant `c`
     pub fn new_with_c(
         stop_condition: StopCondition,
         c: f32,
     ) -> GenericMonteCarloTreeSearchAi<Rules> {
-        GenericMonteCarloTreeSearchAi {
-            stop_condition,
-            last_tree: Default::default(),
-            next_id: 1,
-            c,
-        }
+        GenericMonteCarloTreeSearchAi::new(stop_condition).with_tree_policy(Ucb1 { c })
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum StopCondition {
-    Iterations(usize),
-    Time(Duration),
-}
-
 impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
-    fn do_mcts_iteration(&mut self, tree: Rc<RefCell<Tree<Rules>>>) {
-        let selected_node = selection(tree, self.c);
-        let new_child = self.expansion(selected_node);
-        let result = rollout(Rc::clone(&new_child));
-        new_child.borrow_mut().rewards += &result;
-        new_child.borrow_mut().playouts_from_here += 1.0;
-        backup(Rc::clone(&new_child), result);
-    }
-
-    fn expansion(&mut self, tree: Rc<RefCell<Tree<Rules>>>) -> Rc<RefCell<Tree<Rules>>> {
-        if tree.borrow().state.is_final() {
-            // Can not expand a final state, so just do trivial rollout and backpropagation
-            return tree;
-        }
+    /// How selection balances exploration and exploitation, by default UCB1
+    pub fn with_tree_policy<Policy: TreePolicy + 'static>(
+        mut self,
+        tree_policy: Policy,
+    ) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.tree_policy = Arc::new(tree_policy);
+        self
+    }
 
-        // Choose random move that has not been explored yet
-        let mut possible_moves = tree.borrow().state.get_actions();
-        possible_moves.retain(|m| !tree.borrow().children.contains_key(m));
-        assert!(!possible_moves.is_empty());
+    /// How new nodes are played to the end, by default with uniformly random moves
+    pub fn with_rollout_policy<Policy>(
+        mut self,
+        rollout_policy: Policy,
+    ) -> GenericMonteCarloTreeSearchAi<Rules>
+    where
+        Policy: RolloutPolicy<Rules = Rules> + Send + Sync + 'static,
+    {
+        self.settings.rollout_policy = Arc::new(rollout_policy);
+        self
+    }
+
+    /// Whether to keep searching the subtree of the previous search, if it contains the new
+    /// state. Requires the game to implement `GameStateTrait::hash_key`.
+    pub fn with_tree_reuse(mut self, tree_reuse: bool) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.tree_reuse = tree_reuse;
+        self
+    }
 
-        let random_new_move: Rules::Action = possible_moves
-            .choose(&mut rand::thread_rng())
-            .unwrap()
-            .clone();
+    /// How the iterations are distributed over threads. The stop condition applies to the whole
+    /// search, so `StopCondition::Iterations` counts the playouts of all threads.
+    ///
+    /// Panics if the parallelism has no threads, trees or rollouts.
+    pub fn with_parallelism(
+        mut self,
+        parallelism: Parallelism,
+    ) -> GenericMonteCarloTreeSearchAi<Rules> {
+        assert!(
+            !matches!(
+                parallelism,
+                Parallelism::SharedTree { threads: 0 }
+                    | Parallelism::Root { trees: 0 }
+                    | Parallelism::Leaf { rollouts: 0 }
+            ),
+            "Parallelism needs at least one thread: {:?}",
+            parallelism
+        );
+        self.settings.parallelism = parallelism;
+        self
+    }
 
-        let new_state = Rules::play(&tree.borrow().state, &random_new_move);
+    /// Which child of the root is played after the search, by default the one with the most
+    /// playouts
+    pub fn with_final_move_policy(
+        mut self,
+        final_move_policy: FinalMovePolicy,
+    ) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.final_move_policy = final_move_policy;
+        self
+    }
 
-        let child = Rc::new(RefCell::new(Tree {
-            state: new_state,
-            rewards: Rewards {
-                player_0: 0.0,
-                player_1: 0.0,
-            },
-            playouts_from_here: 0.0,
-            children: FxHashMap::default(),
-            parent: Some(Rc::downgrade(&tree)),
-            id: self.next_id,
-            fully_explored_cache: false,
-        }));
+    /// Whether to back up proven wins and losses (MCTS-Solver). Solved subtrees are not searched
+    /// again, the search stops once the root is solved, and a proven win is played at once.
+    /// Disabled by default.
+    pub fn with_solver(mut self, solver: bool) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.solver = solver;
+        self
+    }
+
+    /// Blends the mean rewards during selection with all-moves-as-first values, which count every
+    /// action played after a node as if it had been played first. Disabled by default.
+    pub fn with_rave(mut self, schedule: RaveSchedule) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.rave = Some(schedule);
+        self
+    }
+
+    /// Prior probabilities of the actions, which order the expansion of children and weigh the
+    /// exploration of PUCT and progressive bias. Uniform by default.
+    pub fn with_move_prior<Prior>(
+        mut self,
+        move_prior: Prior,
+    ) -> GenericMonteCarloTreeSearchAi<Rules>
+    where
+        Prior: MovePrior<Rules = Rules> + Send + Sync + 'static,
+    {
+        self.settings.move_prior = Some(Arc::new(move_prior));
+        self
+    }
 
-        self.next_id += 1;
+    /// Expands further children of a node only as its playouts grow. Disabled by default.
+    pub fn with_progressive_widening(
+        mut self,
+        progressive_widening: ProgressiveWidening,
+    ) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.progressive_widening = Some(progressive_widening);
+        self
+    }
 
-        tree.borrow_mut()
-            .children
-            .insert(random_new_move, Rc::clone(&child));
+    /// Adds `weight * prior / (1 + playouts)` to the score of a child during selection, so the
+    /// prior guides the search until the playouts outweigh it. Zero by default.
+    pub fn with_progressive_bias(mut self, weight: f32) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.progressive_bias = weight;
+        self
+    }
 
-        child
+    /// Scores actions which were not expanded yet as children with the mean reward `urgency`, so
+    /// the tree policy decides whether a node expands another child or selects an expanded one.
+    /// Disabled by default, which expands all children before selecting any.
+    pub fn with_first_play_urgency(mut self, urgency: f32) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.first_play_urgency = Some(urgency);
+        self
+    }
+
+    /// Searches as AlphaZero: The policy of the model provides the priors of PUCT, and new nodes
+    /// are evaluated by the value of the model instead of rollouts. Unexpanded actions count as
+    /// losses, so only actions with a high prior are expanded early.
+    pub fn with_policy_value_model<Model>(
+        self,
+        model: Model,
+    ) -> GenericMonteCarloTreeSearchAi<Rules>
+    where
+        Model: PolicyValueModel<Rules = Rules> + Send + Sync + 'static,
+    {
+        let model = Arc::new(model);
+        self.with_move_prior(ModelPrior(model.clone()))
+            .with_rollout_policy(ModelEvaluation(model))
+            .with_tree_policy(Puct::default())
+            .with_first_play_urgency(0.0)
+    }
+
+    /// Mixes noise into the priors of the root before each search, to explore in self-play.
+    /// Disabled by default.
+    pub fn with_root_noise(
+        mut self,
+        noise: DirichletNoise,
+    ) -> GenericMonteCarloTreeSearchAi<Rules> {
+        self.settings.root_noise = Some(noise);
+        self
     }
 }
 
-impl<Rules: GameRules> GameAi<Rules> for GenericMonteCarloTreeSearchAi<Rules> {
-    fn determine_next_move(&mut self, state: &Rules::State) -> Rules::Action {
-        self.last_tree = Rc::new(RefCell::new(Tree {
-            state: state.clone(),
-            rewards: Rewards {
-                player_0: 0.0,
-                player_1: 0.0,
-            },
-            playouts_from_here: 0.0,
-            children: FxHashMap::default(),
-            parent: None,
-            id: 0,
-            fully_explored_cache: false,
-        }));
-
-        match self.stop_condition {
-            StopCondition::Iterations(iterations) => {
-                for _i in 0..iterations {
-                    self.do_mcts_iteration(Rc::clone(&self.last_tree));
-                }
+impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
+    /// Finds the node of `state` in the last tree: The root itself, one of its children after
+    /// our own move, or a grandchild after the opponent replied.
+    fn find_subtree(&self, state: &Rules::State) -> Option<NodeId> {
+        let key = state.hash_key()?;
+        let tree = &self.last_tree;
+        if tree.is_empty() {
+            return None;
+        }
+        let is_state = |id: &NodeId| tree.node(*id).state.hash_key() == Some(key);
+
+        if is_state(&ROOT) {
+            return Some(ROOT);
+        }
+        for child in tree.children(ROOT).iter().filter_map(|edge| edge.child) {
+            if is_state(&child) {
+                return Some(child);
             }
-            StopCondition::Time(duration) => {
-                let start = Instant::now();
-                while start.elapsed() < duration {
-                    self.do_mcts_iteration(Rc::clone(&self.last_tree));
+            let grandchild = tree
+                .children(child)
+                .iter()
+                .filter_map(|edge| edge.child)
+                .find(is_state);
+            if grandchild.is_some() {
+                return grandchild;
+            }
+        }
+        None
+    }
+
+    /// Makes the node of `state` the root of the last tree, or starts a new tree
+    fn reuse_tree(&mut self, state: &Rules::State) {
+        let subtree = self
+            .settings
+            .tree_reuse
+            .then(|| self.find_subtree(state))
+            .flatten();
+        self.root_noise_added &= subtree == Some(ROOT);
+        self.last_tree = match subtree {
+            Some(ROOT) => std::mem::take(&mut self.last_tree),
+            // Copying the subtree discards all other nodes
+            Some(subtree) => self.last_tree.subtree(subtree),
+            None => Tree::new(state.clone()),
+        };
+    }
+}
+
+impl<Rules> GameAi<Rules> for GenericMonteCarloTreeSearchAi<Rules>
+where
+    Rules: GameRules + 'static,
+    Rules::State: Send + Sync,
+    Rules::Action: Send + Sync,
+{
+    fn determine_next_move(&mut self, state: &Rules::State) -> Rules::Action {
+        self.stop_pondering();
+        self.reuse_tree(state);
+
+        let settings = &self.settings;
+        let tree = &mut self.last_tree;
+        // Mixing the noise in again would compound it, when the root is searched again
+        if let Some(noise) = settings
+            .root_noise
+            .as_ref()
+            .filter(|_| !self.root_noise_added)
+        {
+            noise.add_to_priors(tree, ROOT, settings.move_prior.as_ref());
+            self.root_noise_added = true;
+        }
+        if settings.final_move_policy == FinalMovePolicy::MaxRobustChild {
+            let (main_search, batch) =
+                final_move::split_max_robust_budget(&settings.stop_condition);
+            search_parallel(tree, settings, &main_search);
+            for _batch in 0..final_move::MAX_ROBUST_BATCHES {
+                if is_finished(tree, settings) || final_move::max_robust_child(tree, ROOT).is_some()
+                {
+                    break;
                 }
+                search_parallel(tree, settings, &batch);
             }
+        } else {
+            search_parallel(tree, settings, &settings.stop_condition);
         }
 
-        let best_move = self.last_tree.borrow().select_best_next_move(self.c);
+        report_progress(tree, settings);
+        final_move::select_final_move(tree, ROOT, &settings.final_move_policy)
+            .action
+            .clone()
+    }
 
-        best_move
+    /// Once stopped, plays the final move among the children searched so far
+    fn determine_next_move_with_control(
+        &mut self,
+        state: &Rules::State,
+        control: &SearchControl<Rules::Action>,
+    ) -> Rules::Action {
+        self.settings.control = Some(control.clone());
+        let action = self.determine_next_move(state);
+        self.settings.control = None;
+        action
     }
 
     fn name(&self) -> String {
-        format!("MCTS ({:?})", self.stop_condition)
+        let settings = &self.settings;
+        if settings.parallelism != Parallelism::Sequential {
+            format!(
+                "MCTS ({:?}, {:?})",
+                settings.stop_condition, settings.parallelism
+            )
+        } else {
+            format!("MCTS ({:?})", settings.stop_condition)
+        }
+    }
+
+    /// Grows the tree of `state` sequentially, so the next search continues with the subtree of
+    /// the opponent's move. Requires tree reuse.
+    fn start_pondering(&mut self, state: &Rules::State) {
+        self.stop_pondering();
+        if !self.settings.tree_reuse || state.hash_key().is_none() || state.is_final() {
+            return;
+        }
+        self.reuse_tree(state);
+        let mut tree = std::mem::take(&mut self.last_tree);
+        let settings = self.settings.clone();
+        self.pondering.start(move |stop| {
+            while !stop.load(Ordering::Relaxed)
+                && !solver::is_solved(&tree, ROOT)
+                && tree.len() < MAX_PONDER_TREE_SIZE
+            {
+                do_mcts_iteration(&mut tree, &settings);
+            }
+            tree
+        });
+    }
+
+    fn stop_pondering(&mut self) {
+        if let Some(tree) = self.pondering.stop() {
+            self.last_tree = tree;
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-struct Tree<Rules: GameRules> {
-    state: Rules::State,
-    rewards: Rewards,
-    playouts_from_here: f32,
-    children: FxHashMap<Rules::Action, Rc<RefCell<Tree<Rules>>>>,
-    parent: Option<Weak<RefCell<Tree<Rules>>>>,
-    id: i32,
-    fully_explored_cache: bool,
+fn do_mcts_iteration<Rules: GameRules>(tree: &mut Tree<Rules>, settings: &Settings<Rules>) {
+    let selected_node = selection(tree, settings);
+    let new_child = expansion(tree, selected_node, settings);
+    let playout = settings.rollout_policy.rollout(&tree.node(new_child).state);
+    tree.backup(new_child, &playout.rewards);
+    if settings.rave.is_some() {
+        rave::update_amaf(tree, new_child, &playout);
+    }
+    if settings.solver {
+        solver::prove(tree, new_child);
+    }
 }
 
-impl<Rules: GameRules> Default for Tree<Rules> {
-    fn default() -> Self {
-        Self {
-            state: Default::default(),
-            rewards: Default::default(),
-            playouts_from_here: Default::default(),
-            children: Default::default(),
-            parent: Default::default(),
-            id: Default::default(),
-            fully_explored_cache: false,
-        }
+/// Whether the search is done before its stop condition: The root is solved, or the caller
+/// stopped the search and the root has a child to play
+pub(crate) fn is_finished<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    settings: &Settings<Rules>,
+) -> bool {
+    let stopped = settings
+        .control
+        .as_ref()
+        .is_some_and(|control| control.is_stopped() && !tree.children(ROOT).is_empty());
+    stopped || solver::is_solved(tree, ROOT)
+}
+
+/// Reports the most visited children from the root on and the size of the tree to the control of
+/// the search, if there is one
+pub(crate) fn report_progress<Rules: GameRules>(tree: &Tree<Rules>, settings: &Settings<Rules>) {
+    let Some(control) = &settings.control else {
+        return;
+    };
+    // The most visited children from the root on
+    let mut principal_variation = Vec::new();
+    let mut id = ROOT;
+    while !tree.children(id).is_empty() {
+        let edge = final_move::select_final_move(tree, id, &FinalMovePolicy::RobustChild);
+        principal_variation.push(edge.action.clone());
+        id = edge.child.unwrap();
     }
+    let value = (!tree.children(ROOT).is_empty()).then(|| {
+        let edge = final_move::select_final_move(tree, ROOT, &FinalMovePolicy::RobustChild);
+        child_statistics(tree, ROOT, edge, None).mean_reward()
+    });
+    control.report(SearchProgress {
+        best_move: principal_variation.first().cloned(),
+        depth: tree.max_depth(ROOT),
+        nodes: tree.len() as u64,
+        playouts: tree.node(ROOT).stats.playouts() as u64,
+        value,
+        principal_variation,
+    });
 }
 
-impl<Rules: GameRules> Tree<Rules> {
-    /// Returns true if a child exists for every possible move.
-    fn is_fully_explored(&mut self) -> bool {
-        if self.fully_explored_cache {
-            return true;
+/// Reports progress every `PROGRESS_INTERVAL` during a search loop
+pub(crate) struct ProgressReporter {
+    last_report: Instant,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new() -> ProgressReporter {
+        ProgressReporter {
+            last_report: Instant::now(),
         }
+    }
 
-        let all_moves = self.state.get_actions();
-        let explored = self.children.len() == all_moves.len();
-        self.fully_explored_cache = explored;
-        explored
-    }
-
-    /// Selects child with max UCB1 score
-    fn select_best_next_move(&self, c: f32) -> Rules::Action {
-        assert!(!self.children.is_empty());
-        let parent_playouts = self.playouts_from_here;
-        let player = self.state.next_player();
-        let ucb1 = |child: &Rc<RefCell<Tree<Rules>>>| -> f32 {
-            let child_playouts = child.borrow().playouts_from_here;
-            child.borrow().rewards.for_player(&player) / child_playouts
-                + c * (2.0 * parent_playouts.ln() / child_playouts).sqrt()
-        };
-        self.children
-            .iter()
-            // Child nodes with max UCB1 value
-            .max_set_by(|(_move_1, n1), (_move_2, n2)| ucb1(n1).total_cmp(&ucb1(n2)))
-            // Random tiebreaker
-            .choose(&mut rand::thread_rng())
-            .unwrap()
-            .0
-            .clone()
+    pub(crate) fn tick<Rules: GameRules>(
+        &mut self,
+        tree: &Tree<Rules>,
+        settings: &Settings<Rules>,
+    ) {
+        if settings.control.is_some() && self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            report_progress(tree, settings);
+            self.last_report = Instant::now();
+        }
     }
 }
 
-fn selection<Rules: GameRules>(
-    mut tree: Rc<RefCell<Tree<Rules>>>,
-    c: f32,
-) -> Rc<RefCell<Tree<Rules>>> {
-    // Select node to expand by tree policy, in this case recursively max UCB1 value
-    loop {
-        if tree.borrow().state.is_final() {
-            // Final state can not be expanded
-            return tree;
+/// Searches on the calling thread until `stop_condition` is reached, the root is solved, or the
+/// search is stopped
+pub(crate) fn search<Rules: GameRules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    stop_condition: &StopCondition,
+) {
+    let mut reporter = ProgressReporter::new();
+    match stop_condition {
+        StopCondition::Iterations(iterations) => {
+            for _i in 0..*iterations {
+                if is_finished(tree, settings) {
+                    break;
+                }
+                do_mcts_iteration(tree, settings);
+                reporter.tick(tree, settings);
+            }
+        }
+        StopCondition::Time(duration) => {
+            let start = Instant::now();
+            while start.elapsed() < *duration && !is_finished(tree, settings) {
+                do_mcts_iteration(tree, settings);
+                reporter.tick(tree, settings);
+            }
+        }
+    }
+}
+
+/// Searches until the stop condition is met, with the parallelism of the settings
+fn search_parallel<Rules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    stop_condition: &StopCondition,
+) where
+    Rules: GameRules,
+    Rules::State: Send + Sync,
+    Rules::Action: Send + Sync,
+{
+    match settings.parallelism {
+        Parallelism::Sequential => search(tree, settings, stop_condition),
+        Parallelism::SharedTree { threads } => {
+            parallel::search_shared_tree(tree, settings, stop_condition, threads)
         }
-        if !tree.borrow_mut().is_fully_explored() {
-            return tree;
+        Parallelism::Root { trees } => {
+            parallel::search_root_parallel(tree, settings, stop_condition, trees)
         }
+        Parallelism::Leaf { rollouts } => {
+            parallel::search_leaf_parallel(tree, settings, stop_condition, rollouts)
+        }
+    }
+}
 
-        // Choose child with max UCB1 score
-        let best_move = tree.borrow().select_best_next_move(c);
-        let max_ucb1_child = Rc::clone(tree.borrow().children.get(&best_move).unwrap());
-        tree = max_ucb1_child;
+/// Statistics of the child behind `edge` for the player to move at `parent`. With RAVE, the
+/// reward is blended with the all-moves-as-first value.
+fn child_statistics<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    parent: NodeId,
+    edge: &tree::Edge<Rules::Action>,
+    rave: Option<&RaveSchedule>,
+) -> ChildStatistics {
+    let player = tree.node(parent).state.next_player();
+    let stats = &tree.node(edge.child.unwrap()).stats;
+    let reward = match rave {
+        Some(schedule) => rave::blended_mean(schedule, edge, stats, &player) * stats.visits(),
+        None => stats.reward(&player),
+    };
+    ChildStatistics {
+        visits: stats.visits(),
+        reward,
+        squared_reward: stats.squared_reward(&player),
+        prior: edge.prior,
     }
 }
 
-fn rollout<Rules: GameRules>(tree: Rc<RefCell<Tree<Rules>>>) -> Rewards {
-    return Rules::random_rollout(&tree.borrow().state);
+/// Score of the expanded child of `edge` during selection
+fn child_score<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+    edge: &tree::Edge<Rules::Action>,
+    settings: &Settings<Rules>,
+) -> f32 {
+    let child = child_statistics(tree, id, edge, settings.rave.as_ref());
+    let bias = settings.progressive_bias * child.prior / (1.0 + child.visits);
+    settings
+        .tree_policy
+        .score(&child, tree.node(id).stats.visits())
+        + bias
+}
+
+/// Selects the edge to the child with the highest score of the tree policy. Solved children are
+/// only selected if all children are solved.
+fn select_child<'tree, Rules: GameRules>(
+    tree: &'tree Tree<Rules>,
+    id: NodeId,
+    settings: &Settings<Rules>,
+) -> &'tree tree::Edge<Rules::Action> {
+    let children = tree.children(id);
+    assert!(!children.is_empty());
+    let score = |edge: &tree::Edge<Rules::Action>| child_score(tree, id, edge, settings);
+    let is_solved = |edge: &tree::Edge<Rules::Action>| solver::is_solved(tree, edge.child.unwrap());
+    children
+        .iter()
+        // Unsolved child nodes with max score
+        .max_set_by(|edge_1, edge_2| {
+            is_solved(edge_2)
+                .cmp(&is_solved(edge_1))
+                .then_with(|| score(edge_1).total_cmp(&score(edge_2)))
+        })
+        // Random tiebreaker
+        .choose(&mut rand::thread_rng())
+        .unwrap()
 }
 
-fn backup<Rules: GameRules>(child: Rc<RefCell<Tree<Rules>>>, result: Rewards) {
-    let mut parent = child.as_ref().borrow().parent.clone();
+pub(crate) fn selection<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    settings: &Settings<Rules>,
+) -> NodeId {
+    // Select node to expand by recursively choosing the child with the highest score
+    let mut id = ROOT;
     loop {
-        if parent.is_none() {
-            return;
+        if tree.node(id).is_final {
+            // Final state can not be expanded
+            return id;
+        }
+        if can_expand(tree, id, settings) {
+            return id;
         }
 
-        let parent_rc: Rc<_> = parent.unwrap().upgrade().unwrap();
+        id = select_child(tree, id, settings).child.unwrap();
+    }
+}
+
+/// Whether another child of the node may be expanded, which progressive widening and first play
+/// urgency limit
+pub(crate) fn can_expand<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+    settings: &Settings<Rules>,
+) -> bool {
+    if tree.is_fully_expanded(id) {
+        return false;
+    }
+    if let Some(widening) = &settings.progressive_widening {
+        let max_children = widening.max_children(tree.node(id).stats.playouts());
+        if tree.children(id).len() as u32 >= max_children {
+            return false;
+        }
+    }
+    match settings.first_play_urgency {
+        Some(urgency) => outscores_children(tree, id, settings, urgency),
+        None => true,
+    }
+}
 
-        parent_rc.borrow_mut().playouts_from_here += 1.0;
-        parent_rc.borrow_mut().rewards += &result;
+/// Whether the unexpanded action with the highest prior, scored as a child with the mean reward
+/// `urgency`, beats every unsolved child which is already expanded
+fn outscores_children<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+    settings: &Settings<Rules>,
+    urgency: f32,
+) -> bool {
+    let children = tree.children(id);
+    let prior = tree.edges(id)[children.len()..]
+        .iter()
+        .map(|edge| edge.prior)
+        .fold(0.0, f32::max);
+    let unexpanded = ChildStatistics {
+        visits: 0.0,
+        reward: 0.0,
+        squared_reward: 0.0,
+        prior,
+    };
+    let score = urgency
+        + settings
+            .tree_policy
+            .score(&unexpanded, tree.node(id).stats.visits())
+        + settings.progressive_bias * prior;
+    children
+        .iter()
+        .filter(|edge| !solver::is_solved(tree, edge.child.unwrap()))
+        .all(|edge| {
+            child_score(tree, id, edge, settings)
+                .total_cmp(&score)
+                .is_lt()
+        })
+}
 
-        parent = parent_rc.borrow().parent.clone();
+pub(crate) fn expansion<Rules: GameRules>(
+    tree: &mut Tree<Rules>,
+    id: NodeId,
+    settings: &Settings<Rules>,
+) -> NodeId {
+    if tree.node(id).is_final {
+        // Can not expand a final state, so just do trivial rollout and backpropagation
+        return id;
     }
+    // Choose the move with the highest prior, or a random move, that has not been explored yet
+    tree.expand_child(id, settings.move_prior.as_ref())
 }
 
 impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
     /// Get graphviz representation of last search tree
     pub fn get_last_graphviz(&self) -> Graph {
-        let mut layer = vec![Rc::clone(&self.last_tree)];
+        let tree = &self.last_tree;
+        let mut layer = if tree.is_empty() { vec![] } else { vec![ROOT] };
         let mut layer_index = 0;
 
         let mut graph = graph!(strict di "mcts_tree");
@@ -253,43 +687,45 @@ impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
             let mut subgraph = subgraph!(format!("depth_{}", layer_index));
             subgraph.stmts.push(attr!("rank", "same").into());
 
-            for node in layer.iter() {
-                let node_name = format!("state_{}", node.borrow().id);
-                let node_tooltip = format!("\"{:?}\"", node.borrow().state);
+            for id in layer.iter() {
+                let node = tree.node(*id);
+                let node_name = format!("state_{}", id);
+                let node_tooltip = format!("\"{:?}\"", node.state);
                 let mut dot_node = node!(node_name; attr!("tooltip", node_tooltip));
 
-                let parent_playouts: Option<f32> = node
-                    .borrow()
-                    .parent
-                    .as_ref()
-                    .map(|parent| parent.upgrade().unwrap().borrow().playouts_from_here);
-                let incoming_player = node.borrow().state.incoming_player();
-                let ucb1 = parent_playouts.map(|parent_playouts| {
-                    node.borrow().rewards.for_player(&incoming_player)
-                        / node.borrow().playouts_from_here
-                        + self.c
-                            * (2.0 * parent_playouts.ln() / node.borrow().playouts_from_here).sqrt()
+                let score = node.parent.map(|parent| {
+                    let edge = tree
+                        .children(parent)
+                        .iter()
+                        .find(|edge| edge.child == Some(*id))
+                        .unwrap();
+                    self.settings.tree_policy.score(
+                        &child_statistics(tree, parent, edge, self.settings.rave.as_ref()),
+                        tree.node(parent).stats.visits(),
+                    )
                 });
 
                 let node_label = format!(
                     "\"{}Win/{}Sim ({:.1})\"",
-                    node.borrow().rewards.player_0,
-                    node.borrow().playouts_from_here,
-                    ucb1.unwrap_or(-1.0)
+                    node.stats.reward(&PlayerIndex::ZERO),
+                    node.stats.playouts(),
+                    score.unwrap_or(-1.0)
                 );
                 dot_node.attributes.push(attr!("label", node_label));
 
-                if node.borrow_mut().is_fully_explored() {
+                if tree.is_fully_expanded(*id) {
                     dot_node.attributes.push(attr!("penwidth", 3));
                 }
 
-                if node.borrow().state.is_final() {
-                    let reward = node.borrow().state.reward();
+                if node.is_final {
+                    let reward = node.state.reward();
                     dot_node.attributes.push(attr!("style", "filled"));
 
-                    if reward.player_0 > reward.player_1 {
+                    let reward_0 = reward.for_player(&PlayerIndex::ZERO);
+                    let reward_1 = reward.for_player(&PlayerIndex::ONE);
+                    if reward_0 > reward_1 {
                         dot_node.attributes.push(attr!("fillcolor", "greenyellow"));
-                    } else if reward.player_1 > reward.player_0 {
+                    } else if reward_1 > reward_0 {
                         dot_node.attributes.push(attr!("fillcolor", "red"));
                     } else {
                         dot_node.attributes.push(attr!("fillcolor", "yellow"));
@@ -297,9 +733,9 @@ impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
                 }
                 subgraph.stmts.push(dot_node.into());
 
-                for (action, child) in node.borrow().children.iter() {
-                    let child_name = format!("state_{}", child.borrow().id);
-                    let tooltip = format!("\"{:?}\"", action);
+                for edge in tree.children(*id) {
+                    let child_name = format!("state_{}", edge.child.unwrap());
+                    let tooltip = format!("\"{:?}\"", edge.action);
                     graph.add_stmt(
                         edge!(node_id!(node_name) => node_id!(child_name); attr!("tooltip", tooltip))
                             .into(),
@@ -308,8 +744,8 @@ impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
             }
 
             let mut next_layer = vec![];
-            for node in layer {
-                next_layer.extend(node.borrow().children.values().map(Rc::clone))
+            for id in layer {
+                next_layer.extend(tree.children(id).iter().filter_map(|edge| edge.child))
             }
 
             graph.add_stmt(subgraph.into());
@@ -322,50 +758,117 @@ impl<Rules: GameRules> GenericMonteCarloTreeSearchAi<Rules> {
     }
 
     pub fn tree_size(&self) -> usize {
-        self.last_tree.borrow().tree_size()
+        self.last_tree.len()
     }
 
-    pub fn tree_depth(&self) -> usize {
-        self.last_tree.borrow().max_depth()
+    /// Number of playouts through the root, including those of reused searches. 0 before the
+    /// first search and while pondering.
+    pub fn tree_playouts(&self) -> usize {
+        if self.last_tree.is_empty() {
+            return 0;
+        }
+        self.last_tree.node(ROOT).stats.playouts() as usize
     }
 
-    pub fn tree_branching_factor(&self) -> f32 {
-        self.last_tree.borrow().average_branching_factor()
+    /// Share of the playouts of every child of the root in the last search, a training target
+    /// for the policy of a model. Empty before the first search and while pondering.
+    pub fn root_policy(&self) -> Vec<(Rules::Action, f32)> {
+        let tree = &self.last_tree;
+        if tree.is_empty() {
+            return Vec::new();
+        }
+        let children = tree.children(ROOT);
+        let playouts: f32 = children
+            .iter()
+            .map(|edge| tree.node(edge.child.unwrap()).stats.playouts())
+            .sum();
+        children
+            .iter()
+            .map(|edge| {
+                let child_playouts = tree.node(edge.child.unwrap()).stats.playouts();
+                (edge.action.clone(), child_playouts / playouts)
+            })
+            .collect()
     }
-}
 
-impl<Rules: GameRules> Tree<Rules> {
-    fn tree_size(&self) -> usize {
-        1 + self
-            .children
-            .values()
-            .map(|child| child.borrow().tree_size())
-            .sum::<usize>()
+    /// Rewards of the root with perfect play, if the last search solved it
+    pub fn proven_rewards(&self) -> Option<Rewards> {
+        if self.last_tree.is_empty() {
+            return None;
+        }
+        self.last_tree.node(ROOT).proven.get().cloned()
     }
 
-    fn leaf_nodes_count(&self) -> usize {
-        if self.children.is_empty() {
-            1
-        } else {
-            self.children
-                .values()
-                .map(|child| child.borrow().leaf_nodes_count())
-                .sum::<usize>()
+    pub fn tree_depth(&self) -> usize {
+        if self.last_tree.is_empty() {
+            return 0;
         }
+        self.last_tree.max_depth(ROOT)
     }
 
-    fn average_branching_factor(&self) -> f32 {
-        let tree_size = self.tree_size();
-        let non_leaf_nodes = tree_size - self.leaf_nodes_count();
+    /// Average number of children of the expanded nodes, 0 if no node was expanded
+    pub fn tree_branching_factor(&self) -> f32 {
+        let tree_size = self.last_tree.len();
+        let non_leaf_nodes = tree_size - self.last_tree.leaf_nodes_count();
+        if non_leaf_nodes == 0 {
+            return 0.0;
+        }
         (tree_size - 1) as f32 / non_leaf_nodes as f32
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_ai::{GameAi, StopCondition};
+    use hexxagon_lib::{ai::CaptureHeuristic, game::rules::HexxagonRules, game::GameState};
+
+    use tic_tac_toe::{TTTRules, TTTState};
+
+    use super::GenericMonteCarloTreeSearchAi;
+    use crate::prior::{HeuristicPrior, ProgressiveWidening};
+    use crate::tree::{Tree, ROOT};
+    use crate::DirichletNoise;
+
+    fn sorted_root_priors(tree: &Tree<TTTRules>) -> Vec<f32> {
+        let mut priors = tree
+            .edges(ROOT)
+            .iter()
+            .map(|edge| edge.prior)
+            .collect::<Vec<_>>();
+        priors.sort_by(f32::total_cmp);
+        priors
+    }
+
+    #[test]
+    fn progressive_widening_expands_children_by_prior() {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(100))
+                .with_move_prior(HeuristicPrior::new(CaptureHeuristic {}, 1.0))
+                .with_progressive_widening(ProgressiveWidening { c: 1.0, alpha: 0.5 });
+        ai.determine_next_move(&GameState::default());
+
+        let tree = &ai.last_tree;
+        let expanded = tree.children(ROOT);
+        // The tenth child was expanded after 81 playouts
+        assert_eq!(expanded.len(), 10);
+        let min_expanded_prior = expanded
+            .iter()
+            .map(|edge| edge.prior)
+            .fold(f32::INFINITY, f32::min);
+        assert!(tree.edges(ROOT)[expanded.len()..]
+            .iter()
+            .all(|edge| edge.prior <= min_expanded_prior));
+    }
 
-    fn max_depth(&self) -> usize {
-        1 + self
-            .children
-            .values()
-            .map(|child| child.borrow().max_depth())
-            .max()
-            .unwrap_or(0)
+    #[test]
+    fn root_noise_is_added_once_per_root() {
+        let state = TTTState::default();
+        let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(20))
+            .with_root_noise(DirichletNoise::default());
+        ai.determine_next_move(&state);
+        let priors = sorted_root_priors(&ai.last_tree);
+        // The reused root keeps the noise of the first search
+        ai.determine_next_move(&state);
+        assert_eq!(sorted_root_priors(&ai.last_tree), priors);
     }
 }
@@ -0,0 +1,128 @@
+//! AlphaZero style search: A policy value model provides the priors of PUCT and evaluates new
+//! nodes instead of rollouts
+
+use std::sync::Arc;
+
+use game_ai::{GameRules, GameStateTrait, PolicyValueModel};
+use itertools::Itertools;
+use rand_distr::{Dirichlet, Distribution};
+
+use crate::prior::{MovePrior, SharedMovePrior};
+use crate::rollout::{Playout, RolloutPolicy};
+use crate::tree::{NodeId, Tree};
+
+/// Priors from the policy of the model
+pub(crate) struct ModelPrior<Model>(pub(crate) Arc<Model>);
+
+impl<Model: PolicyValueModel> MovePrior for ModelPrior<Model> {
+    type Rules = Model::Rules;
+
+    fn priors(
+        &self,
+        state: &<Model::Rules as GameRules>::State,
+        actions: &[<Model::Rules as GameRules>::Action],
+    ) -> Vec<f32> {
+        self.0.policy(state, actions)
+    }
+}
+
+/// Evaluates new nodes by the value of the model, without playing any moves
+pub(crate) struct ModelEvaluation<Model>(pub(crate) Arc<Model>);
+
+impl<Model: PolicyValueModel> RolloutPolicy for ModelEvaluation<Model> {
+    type Rules = Model::Rules;
+
+    /// Action with the highest probability of the policy
+    fn choose_action(
+        &self,
+        state: &<Model::Rules as GameRules>::State,
+    ) -> <Model::Rules as GameRules>::Action {
+        let actions = state.get_actions();
+        let policy = self.0.policy(state, &actions);
+        let best = policy
+            .iter()
+            .position_max_by(|probability_1, probability_2| probability_1.total_cmp(probability_2))
+            .expect("Rollout failed, no actions possible!");
+        actions[best].clone()
+    }
+
+    fn rollout(&self, state: &<Model::Rules as GameRules>::State) -> Playout<Model::Rules> {
+        let rewards = if state.is_final() {
+            state.reward()
+        } else {
+            self.0.value(state)
+        };
+        Playout {
+            rewards,
+            actions: Vec::new(),
+        }
+    }
+}
+
+/// Noise mixed into the priors of the root before each search, so that self-play explores
+/// different moves: `prior = (1 - fraction) * prior + fraction * Dirichlet(alpha)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirichletNoise {
+    pub alpha: f32,
+    pub fraction: f32,
+}
+
+impl Default for DirichletNoise {
+    fn default() -> Self {
+        DirichletNoise {
+            alpha: 0.3,
+            fraction: 0.25,
+        }
+    }
+}
+
+impl DirichletNoise {
+    pub(crate) fn add_to_priors<Rules: GameRules>(
+        &self,
+        tree: &mut Tree<Rules>,
+        id: NodeId,
+        move_prior: Option<&SharedMovePrior<Rules>>,
+    ) {
+        let edges = tree.edges_mut(id, move_prior);
+        // The distribution needs at least two actions
+        if edges.len() < 2 {
+            return;
+        }
+        let noise = Dirichlet::new_with_size(self.alpha, edges.len())
+            .unwrap()
+            .sample(&mut rand::thread_rng());
+        for (edge, noise) in edges.iter_mut().zip(noise) {
+            edge.prior = (1.0 - self.fraction) * edge.prior + self.fraction * noise;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use game_ai::{GameStateTrait, LinearModel};
+    use tic_tac_toe::{TTTFeatures, TTTRules, TTTState};
+
+    use super::{DirichletNoise, ModelPrior};
+    use crate::prior::SharedMovePrior;
+    use crate::tree::{Tree, ROOT};
+
+    #[test]
+    fn noise_keeps_priors_probabilities() {
+        let model = Arc::new(LinearModel::new(TTTFeatures {}));
+        let move_prior: SharedMovePrior<TTTRules> = Arc::new(ModelPrior(model));
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        DirichletNoise::default().add_to_priors(&mut tree, ROOT, Some(&move_prior));
+
+        let priors = tree
+            .edges(ROOT)
+            .iter()
+            .map(|edge| edge.prior)
+            .collect::<Vec<_>>();
+        assert_eq!(priors.len(), TTTState::default().get_actions().len());
+        assert!((priors.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        // The untrained model is uniform, so only the noise makes the priors differ
+        assert!(priors.iter().any(|prior| (prior - 1.0 / 9.0).abs() > 1e-6));
+    }
+}
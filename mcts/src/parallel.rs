@@ -0,0 +1,214 @@
+//! Searches which distribute the MCTS iterations over several threads
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::Instant;
+
+use game_ai::{GameRules, SearchControl, StopCondition};
+use itertools::Itertools;
+use rayon::prelude::*;
+
+use crate::mcts_generic::{
+    can_expand, expansion, is_finished, search, selection, ProgressReporter, Settings,
+};
+use crate::tree::{Tree, ROOT};
+use crate::{rave, solver};
+
+/// How the iterations are distributed over threads
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parallelism {
+    /// Search everything on the calling thread
+    Sequential,
+    /// `threads` threads search the same tree. Virtual losses spread them over different paths.
+    SharedTree { threads: usize },
+    /// `trees` independent trees are searched in parallel, then they are merged into one
+    Root { trees: usize },
+    /// Every expanded node is evaluated by `rollouts` playouts in parallel
+    Leaf { rollouts: usize },
+}
+
+pub(crate) fn search_shared_tree<Rules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    stop_condition: &StopCondition,
+    threads: usize,
+) where
+    Rules: GameRules,
+    Rules::State: Send + Sync,
+    Rules::Action: Send + Sync,
+{
+    let shared_tree = RwLock::new(std::mem::take(tree));
+    let started_iterations = AtomicUsize::new(0);
+    let start = Instant::now();
+    let keep_searching = || match stop_condition {
+        StopCondition::Iterations(iterations) => {
+            started_iterations.fetch_add(1, Ordering::Relaxed) < *iterations
+        }
+        StopCondition::Time(duration) => start.elapsed() < *duration,
+    };
+
+    thread::scope(|scope| {
+        for thread in 0..threads {
+            let (shared_tree, keep_searching) = (&shared_tree, &keep_searching);
+            scope.spawn(move || {
+                let mut reporter = ProgressReporter::new();
+                while !is_finished(&shared_tree.read().unwrap(), settings) && keep_searching() {
+                    do_shared_mcts_iteration(shared_tree, settings);
+                    // The first thread reports the progress of all
+                    if thread == 0 {
+                        reporter.tick(&shared_tree.read().unwrap(), settings);
+                    }
+                }
+            });
+        }
+    });
+    *tree = shared_tree.into_inner().unwrap();
+}
+
+/// MCTS iteration on a tree shared with other threads. Selection and backup only need shared
+/// access, so the tree is locked exclusively just to expand a node.
+fn do_shared_mcts_iteration<Rules: GameRules>(
+    tree: &RwLock<Tree<Rules>>,
+    settings: &Settings<Rules>,
+) {
+    let (new_child, state) = loop {
+        let shared_tree = tree.read().unwrap();
+        let selected_node = selection(&shared_tree, settings);
+        if shared_tree.node(selected_node).is_final {
+            // Can not expand a final state, so just do trivial rollout and backpropagation
+            shared_tree.add_virtual_loss(selected_node);
+            break (selected_node, shared_tree.node(selected_node).state.clone());
+        }
+        drop(shared_tree);
+
+        let mut exclusive_tree = tree.write().unwrap();
+        // Another thread may have expanded the last child in the meantime
+        if can_expand(&exclusive_tree, selected_node, settings) {
+            let new_child = expansion(&mut exclusive_tree, selected_node, settings);
+            exclusive_tree.add_virtual_loss(new_child);
+            break (new_child, exclusive_tree.node(new_child).state.clone());
+        }
+    };
+    let playout = settings.rollout_policy.rollout(&state);
+    let shared_tree = tree.read().unwrap();
+    shared_tree.backup_shared(new_child, &playout.rewards);
+    if settings.rave.is_some() {
+        rave::update_amaf(&shared_tree, new_child, &playout);
+    }
+    if settings.solver {
+        solver::prove(&shared_tree, new_child);
+    }
+}
+
+pub(crate) fn search_root_parallel<Rules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    stop_condition: &StopCondition,
+    trees: usize,
+) where
+    Rules: GameRules,
+    Rules::State: Send,
+    Rules::Action: Send,
+{
+    // Each tree gets its share of the iterations
+    let stop_condition_of_tree = |index: usize| match stop_condition {
+        StopCondition::Iterations(iterations) => {
+            StopCondition::Iterations(iterations / trees + usize::from(index < iterations % trees))
+        }
+        StopCondition::Time(duration) => StopCondition::Time(*duration),
+    };
+
+    // Only the progress of the calling thread's tree is reported
+    let other_settings = Settings {
+        control: settings.control.as_ref().map(SearchControl::silent),
+        ..settings.clone()
+    };
+    let other_trees: Vec<Tree<Rules>> = thread::scope(|scope| {
+        let handles = (1..trees)
+            .map(|index| {
+                let state = tree.node(ROOT).state.clone();
+                let stop_condition = stop_condition_of_tree(index);
+                let other_settings = &other_settings;
+                scope.spawn(move || {
+                    let mut other_tree = Tree::new(state);
+                    search(&mut other_tree, other_settings, &stop_condition);
+                    other_tree
+                })
+            })
+            .collect_vec();
+        // The calling thread continues the (possibly reused) tree
+        search(tree, settings, &stop_condition_of_tree(0));
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    for other_tree in &other_trees {
+        tree.merge(other_tree);
+    }
+    if settings.solver {
+        // The children may have been proven in different trees
+        solver::prove(tree, ROOT);
+    }
+}
+
+pub(crate) fn search_leaf_parallel<Rules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    stop_condition: &StopCondition,
+    rollouts: usize,
+) where
+    Rules: GameRules,
+    Rules::State: Send + Sync,
+    Rules::Action: Send,
+{
+    let mut reporter = ProgressReporter::new();
+    match stop_condition {
+        StopCondition::Iterations(iterations) => {
+            let mut remaining = *iterations;
+            while remaining > 0 && !is_finished(tree, settings) {
+                // The last iteration only plays the playouts that are left
+                let batch = rollouts.min(remaining);
+                do_leaf_parallel_mcts_iteration(tree, settings, batch);
+                remaining -= batch;
+                reporter.tick(tree, settings);
+            }
+        }
+        StopCondition::Time(duration) => {
+            let start = Instant::now();
+            while start.elapsed() < *duration && !is_finished(tree, settings) {
+                do_leaf_parallel_mcts_iteration(tree, settings, rollouts);
+                reporter.tick(tree, settings);
+            }
+        }
+    }
+}
+
+/// MCTS iteration which evaluates the new node by `rollouts` playouts on the rayon thread pool
+fn do_leaf_parallel_mcts_iteration<Rules>(
+    tree: &mut Tree<Rules>,
+    settings: &Settings<Rules>,
+    rollouts: usize,
+) where
+    Rules: GameRules,
+    Rules::State: Send + Sync,
+    Rules::Action: Send,
+{
+    let selected_node = selection(tree, settings);
+    let new_child = expansion(tree, selected_node, settings);
+    let state = &tree.node(new_child).state;
+    let playouts: Vec<_> = (0..rollouts)
+        .into_par_iter()
+        .map(|_| settings.rollout_policy.rollout(state))
+        .collect();
+    for playout in &playouts {
+        tree.backup(new_child, &playout.rewards);
+        if settings.rave.is_some() {
+            rave::update_amaf(tree, new_child, playout);
+        }
+    }
+    if settings.solver {
+        solver::prove(tree, new_child);
+    }
+}
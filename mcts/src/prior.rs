@@ -0,0 +1,125 @@
+//! Prior probabilities of moves, which focus the search on promising moves when there are many
+
+use std::sync::Arc;
+
+use game_ai::{ActionHeuristic, GameRules};
+use itertools::Itertools;
+
+/// Prior probabilities of the actions of a state, before any playouts
+pub trait MovePrior {
+    type Rules: GameRules;
+
+    /// Priors of `actions`, which are all possible actions in `state`. They are normalized to
+    /// sum to one.
+    fn priors(
+        &self,
+        state: &<Self::Rules as GameRules>::State,
+        actions: &[<Self::Rules as GameRules>::Action],
+    ) -> Vec<f32>;
+}
+
+pub(crate) type SharedMovePrior<Rules> = Arc<dyn MovePrior<Rules = Rules> + Send + Sync>;
+
+/// Priors from the scores of an action heuristic, by the softmax function. Higher temperatures
+/// make the priors more uniform.
+pub struct HeuristicPrior<Heuristic> {
+    heuristic: Heuristic,
+    temperature: f32,
+}
+
+impl<Heuristic: ActionHeuristic> HeuristicPrior<Heuristic> {
+    pub fn new(heuristic: Heuristic, temperature: f32) -> HeuristicPrior<Heuristic> {
+        HeuristicPrior {
+            heuristic,
+            temperature,
+        }
+    }
+}
+
+impl<Heuristic: ActionHeuristic> MovePrior for HeuristicPrior<Heuristic> {
+    type Rules = Heuristic::Rules;
+
+    fn priors(
+        &self,
+        state: &<Heuristic::Rules as GameRules>::State,
+        actions: &[<Heuristic::Rules as GameRules>::Action],
+    ) -> Vec<f32> {
+        let scores = actions
+            .iter()
+            .map(|action| self.heuristic.score(state, action) / self.temperature)
+            .collect_vec();
+        // Subtracting the maximum keeps the exponentials finite
+        let max_score = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let weights = scores
+            .into_iter()
+            .map(|score| (score - max_score).exp())
+            .collect_vec();
+        let sum: f32 = weights.iter().sum();
+        weights.into_iter().map(|weight| weight / sum).collect()
+    }
+}
+
+/// Limits the children of a node to `ceil(c * playouts^alpha)`, which are expanded in order of
+/// their prior
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressiveWidening {
+    pub c: f32,
+    pub alpha: f32,
+}
+
+impl Default for ProgressiveWidening {
+    fn default() -> Self {
+        ProgressiveWidening { c: 1.0, alpha: 0.5 }
+    }
+}
+
+impl ProgressiveWidening {
+    /// Number of children a node with `playouts` may have, at least one
+    pub(crate) fn max_children(&self, playouts: f32) -> u32 {
+        ((self.c * playouts.powf(self.alpha)).ceil() as u32).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_ai::{ActionHeuristic, GameStateTrait};
+    use tic_tac_toe::{TTTAction, TTTRules, TTTState};
+
+    use super::{HeuristicPrior, MovePrior, ProgressiveWidening};
+
+    /// Prefers actions which come first
+    struct FirstActions {}
+
+    impl ActionHeuristic for FirstActions {
+        type Rules = TTTRules;
+
+        fn score(&self, state: &TTTState, action: &TTTAction) -> f32 {
+            let index = state
+                .get_actions()
+                .iter()
+                .position(|a| a == action)
+                .unwrap();
+            -(index as f32)
+        }
+    }
+
+    #[test]
+    fn heuristic_priors_are_probabilities() {
+        let state = TTTState::default();
+        let actions = state.get_actions();
+        let priors = HeuristicPrior::new(FirstActions {}, 1.0).priors(&state, &actions);
+        assert!((priors.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(priors.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let flat_priors = HeuristicPrior::new(FirstActions {}, 100.0).priors(&state, &actions);
+        assert!(flat_priors[0] < priors[0]);
+    }
+
+    #[test]
+    fn widening_grows_with_playouts() {
+        let widening = ProgressiveWidening::default();
+        assert_eq!(widening.max_children(0.0), 1);
+        assert_eq!(widening.max_children(1.0), 1);
+        assert_eq!(widening.max_children(100.0), 10);
+    }
+}
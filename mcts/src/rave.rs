@@ -0,0 +1,143 @@
+//! Rapid action value estimation (RAVE): Every action played after a node counts as if it had been
+//! played first, which gives early estimates for actions that are good in many positions
+
+use game_ai::{GameRules, GameStateTrait, PlayerIndex};
+use rustc_hash::FxHashSet;
+
+use crate::rollout::Playout;
+use crate::tree::{Edge, NodeId, NodeStats, Tree};
+
+/// How the weight `beta` of the all-moves-as-first value decreases with the playouts of a child
+#[derive(Clone, Debug, PartialEq)]
+pub enum RaveSchedule {
+    /// `beta = sqrt(k / (3 playouts + k))`, so both values weigh the same after `k` playouts
+    Equivalence { k: f32 },
+    /// `beta = amaf / (playouts + amaf + 4 bias² playouts amaf)`, which minimizes the mean squared
+    /// error if the all-moves-as-first values are off by `bias`
+    MinimumMse { bias: f32 },
+}
+
+impl RaveSchedule {
+    fn beta(&self, playouts: f32, amaf_playouts: f32) -> f32 {
+        match self {
+            RaveSchedule::Equivalence { k } => (k / (3.0 * playouts + k)).sqrt(),
+            RaveSchedule::MinimumMse { bias } => {
+                amaf_playouts
+                    / (playouts + amaf_playouts + 4.0 * bias * bias * playouts * amaf_playouts)
+            }
+        }
+    }
+}
+
+/// Mean reward of the child behind `edge` for `player`, blended with the all-moves-as-first value
+pub(crate) fn blended_mean<Action>(
+    schedule: &RaveSchedule,
+    edge: &Edge<Action>,
+    stats: &NodeStats,
+    player: &PlayerIndex,
+) -> f32 {
+    let visits = stats.visits();
+    let mean = if visits > 0.0 {
+        stats.reward(player) / visits
+    } else {
+        0.0
+    };
+    let amaf_playouts = edge.amaf.visits();
+    if amaf_playouts == 0.0 {
+        return mean;
+    }
+    let beta = schedule.beta(visits, amaf_playouts);
+    (1.0 - beta) * mean + beta * edge.amaf.reward() / amaf_playouts
+}
+
+/// Adds the playout from node `id` to the all-moves-as-first statistics of the node and all its
+/// ancestors. Players are assumed to take turns in order of their index.
+pub(crate) fn update_amaf<Rules: GameRules>(
+    tree: &Tree<Rules>,
+    id: NodeId,
+    playout: &Playout<Rules>,
+) {
+    let mut played = FxHashSet::default();
+    let mut player = tree.node(id).state.next_player();
+    for action in &playout.actions {
+        played.insert((player, action));
+        player = player.next(Rules::N_PLAYERS);
+    }
+
+    let mut current = id;
+    loop {
+        let node = tree.node(current);
+        let player = node.state.next_player();
+        for edge in tree.edges(current) {
+            if played.contains(&(player, &edge.action)) {
+                edge.amaf.add_playout(playout.rewards.for_player(&player));
+            }
+        }
+        let Some(parent) = node.parent else {
+            return;
+        };
+        let edge = tree
+            .children(parent)
+            .iter()
+            .find(|edge| edge.child == Some(current))
+            .unwrap();
+        played.insert((tree.node(parent).state.next_player(), &edge.action));
+        current = parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards};
+    use tic_tac_toe::{TTTRules, TTTState};
+
+    use super::{update_amaf, RaveSchedule};
+    use crate::rollout::Playout;
+    use crate::tree::{Tree, ROOT};
+
+    #[test]
+    fn schedules_decrease_with_playouts() {
+        for schedule in [
+            RaveSchedule::Equivalence { k: 100.0 },
+            RaveSchedule::MinimumMse { bias: 0.1 },
+        ] {
+            assert!(schedule.beta(10.0, 50.0) > schedule.beta(1000.0, 50.0));
+            assert!((0.0..=1.0).contains(&schedule.beta(10.0, 50.0)));
+        }
+        assert_eq!(RaveSchedule::Equivalence { k: 100.0 }.beta(0.0, 10.0), 1.0);
+    }
+
+    #[test]
+    fn rollout_actions_update_ancestors() {
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        let child = tree.expand_random_child(ROOT);
+        // Create the edges of the child
+        tree.expand_random_child(child);
+
+        // The second player replies with `second`, then the first player plays `third`
+        let state = tree.node(child).state.clone();
+        let second = state.get_actions()[0].clone();
+        let third = TTTRules::play(&state, &second).get_actions()[0].clone();
+        update_amaf(
+            &tree,
+            child,
+            &Playout {
+                rewards: Rewards::new(&[1.0, 0.0]),
+                actions: vec![second.clone(), third.clone()],
+            },
+        );
+
+        for edge in tree.edges(ROOT) {
+            let played = edge.child == Some(child) || edge.action == third;
+            assert_eq!(edge.amaf.visits(), if played { 1.0 } else { 0.0 });
+            assert_eq!(edge.amaf.reward(), if played { 1.0 } else { 0.0 });
+        }
+        for edge in tree.edges(child) {
+            let played = edge.action == second;
+            assert_eq!(edge.amaf.visits(), if played { 1.0 } else { 0.0 });
+            // The second player lost
+            assert_eq!(edge.amaf.reward(), 0.0);
+        }
+        assert_eq!(tree.node(child).state.next_player(), PlayerIndex::ONE);
+    }
+}
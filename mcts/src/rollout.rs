@@ -0,0 +1,249 @@
+//! Policies which play the game on from a new node, to estimate its value
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use game_ai::{ActionHeuristic, Evaluator, GameRules, GameStateTrait, PlayerIndex, Rewards};
+use itertools::Itertools;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How moves are chosen during a rollout
+pub trait RolloutPolicy {
+    type Rules: GameRules;
+
+    /// Action to play in `state`, which is not final
+    fn choose_action(
+        &self,
+        state: &<Self::Rules as GameRules>::State,
+    ) -> <Self::Rules as GameRules>::Action;
+
+    /// Plays the game from `state` until the end, and returns the rewards of all players and the
+    /// played actions, in order
+    fn rollout(&self, state: &<Self::Rules as GameRules>::State) -> Playout<Self::Rules> {
+        let mut state = state.clone();
+        let mut actions = Vec::new();
+        while !state.is_final() {
+            let action = self.choose_action(&state);
+            state = Self::Rules::play(&state, &action);
+            actions.push(action);
+        }
+        Playout {
+            rewards: state.reward(),
+            actions,
+        }
+    }
+}
+
+/// Rewards of a rollout and the actions played in it
+pub struct Playout<Rules: GameRules> {
+    pub rewards: Rewards,
+    /// Played actions, in order
+    pub actions: Vec<Rules::Action>,
+}
+
+impl<Rules: GameRules> std::fmt::Debug for Playout<Rules> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Playout")
+            .field("rewards", &self.rewards)
+            .field("actions", &self.actions)
+            .finish()
+    }
+}
+
+impl<Rules: GameRules> PartialEq for Playout<Rules> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rewards == other.rewards && self.actions == other.actions
+    }
+}
+
+pub(crate) type SharedRolloutPolicy<Rules> = Arc<dyn RolloutPolicy<Rules = Rules> + Send + Sync>;
+
+fn random_action<Rules: GameRules>(state: &Rules::State) -> Rules::Action {
+    state
+        .get_actions()
+        .choose(&mut rand::thread_rng())
+        .expect("Rollout failed, no actions possible!")
+        .clone()
+}
+
+/// Rewards for a state which is not played to the end: The logistic function of each player's
+/// value. Larger scales make the rewards less decisive.
+fn evaluated_rewards<Eval: Evaluator>(
+    evaluator: &Eval,
+    state: &<Eval::Rules as GameRules>::State,
+    scale: f32,
+) -> Rewards {
+    let rewards = PlayerIndex::all(Eval::Rules::N_PLAYERS)
+        .map(|player| 1.0 / (1.0 + (-evaluator.value(state, &player) / scale).exp()))
+        .collect_vec();
+    Rewards::new(&rewards)
+}
+
+/// Plays uniformly random moves with `GameRules::random_playout`
+pub struct UniformRollout<Rules> {
+    rules: PhantomData<fn() -> Rules>,
+}
+
+impl<Rules> Default for UniformRollout<Rules> {
+    fn default() -> Self {
+        UniformRollout { rules: PhantomData }
+    }
+}
+
+impl<Rules: GameRules> RolloutPolicy for UniformRollout<Rules> {
+    type Rules = Rules;
+
+    fn choose_action(&self, state: &Rules::State) -> Rules::Action {
+        random_action::<Rules>(state)
+    }
+
+    fn rollout(&self, state: &Rules::State) -> Playout<Rules> {
+        let (rewards, actions) = Rules::random_playout(state);
+        Playout { rewards, actions }
+    }
+}
+
+/// Plays a random move with probability `epsilon`, otherwise the move leading to the state with
+/// the highest value
+pub struct EpsilonGreedyRollout<Eval> {
+    evaluator: Eval,
+    epsilon: f32,
+}
+
+impl<Eval: Evaluator> EpsilonGreedyRollout<Eval> {
+    pub fn new(evaluator: Eval, epsilon: f32) -> EpsilonGreedyRollout<Eval> {
+        EpsilonGreedyRollout { evaluator, epsilon }
+    }
+}
+
+impl<Eval: Evaluator> RolloutPolicy for EpsilonGreedyRollout<Eval> {
+    type Rules = Eval::Rules;
+
+    fn choose_action(
+        &self,
+        state: &<Eval::Rules as GameRules>::State,
+    ) -> <Eval::Rules as GameRules>::Action {
+        let mut rng = rand::thread_rng();
+        if rng.gen::<f32>() < self.epsilon {
+            return random_action::<Eval::Rules>(state);
+        }
+        let player = state.next_player();
+        let value = |action: &<Eval::Rules as GameRules>::Action| {
+            self.evaluator
+                .value(&Eval::Rules::play(state, action), &player)
+        };
+        state
+            .get_actions()
+            .into_iter()
+            .max_set_by(|action_1, action_2| value(action_1).total_cmp(&value(action_2)))
+            .choose(&mut rng)
+            .expect("Rollout failed, no actions possible!")
+            .clone()
+    }
+}
+
+/// Samples moves in proportion to `base_weight` plus their heuristic score, so that moves
+/// without a score are still played now and then
+pub struct HeuristicRollout<Heuristic> {
+    heuristic: Heuristic,
+    base_weight: f32,
+}
+
+impl<Heuristic: ActionHeuristic> HeuristicRollout<Heuristic> {
+    pub fn new(heuristic: Heuristic, base_weight: f32) -> HeuristicRollout<Heuristic> {
+        HeuristicRollout {
+            heuristic,
+            base_weight,
+        }
+    }
+}
+
+impl<Heuristic: ActionHeuristic> RolloutPolicy for HeuristicRollout<Heuristic> {
+    type Rules = Heuristic::Rules;
+
+    fn choose_action(
+        &self,
+        state: &<Heuristic::Rules as GameRules>::State,
+    ) -> <Heuristic::Rules as GameRules>::Action {
+        let mut actions = state.get_actions();
+        let weights = actions
+            .iter()
+            .map(|action| self.base_weight + self.heuristic.score(state, action).max(0.0))
+            .collect_vec();
+        match WeightedIndex::new(weights) {
+            Ok(distribution) => actions.swap_remove(distribution.sample(&mut rand::thread_rng())),
+            // All weights are zero
+            Err(_) => random_action::<Heuristic::Rules>(state),
+        }
+    }
+}
+
+/// Plays `plies` moves with another policy, then scores the state with an evaluator instead of
+/// playing on until the end
+pub struct TruncatedRollout<Policy, Eval> {
+    policy: Policy,
+    evaluator: Eval,
+    plies: usize,
+    scale: f32,
+}
+
+impl<Policy, Eval> TruncatedRollout<Policy, Eval>
+where
+    Policy: RolloutPolicy,
+    Eval: Evaluator<Rules = Policy::Rules>,
+{
+    /// # Arguments
+    ///
+    /// * `scale` - Evaluator value, at which a player's reward is 0.73 (the logistic function
+    ///   of 1)
+    pub fn new(
+        policy: Policy,
+        evaluator: Eval,
+        plies: usize,
+        scale: f32,
+    ) -> TruncatedRollout<Policy, Eval> {
+        TruncatedRollout {
+            policy,
+            evaluator,
+            plies,
+            scale,
+        }
+    }
+}
+
+impl<Policy, Eval> RolloutPolicy for TruncatedRollout<Policy, Eval>
+where
+    Policy: RolloutPolicy,
+    Eval: Evaluator<Rules = Policy::Rules>,
+{
+    type Rules = Policy::Rules;
+
+    fn choose_action(
+        &self,
+        state: &<Policy::Rules as GameRules>::State,
+    ) -> <Policy::Rules as GameRules>::Action {
+        self.policy.choose_action(state)
+    }
+
+    fn rollout(&self, state: &<Policy::Rules as GameRules>::State) -> Playout<Policy::Rules> {
+        let mut state = state.clone();
+        let mut actions = Vec::new();
+        for _ply in 0..self.plies {
+            if state.is_final() {
+                break;
+            }
+            let action = self.policy.choose_action(&state);
+            state = Policy::Rules::play(&state, &action);
+            actions.push(action);
+        }
+        let rewards = if state.is_final() {
+            state.reward()
+        } else {
+            evaluated_rewards(&self.evaluator, &state, self.scale)
+        };
+        Playout { rewards, actions }
+    }
+}
@@ -0,0 +1,126 @@
+//! MCTS-Solver: Game theoretic values of final states are backed up through the tree, so that
+//! solved subtrees need not be searched any further
+
+use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards};
+
+use crate::tree::{NodeId, Tree};
+
+/// Reward of a won game. Rewards are between 0 and 1, as the tree policies assume.
+const WIN_REWARD: f32 = 1.0;
+/// Reward of a lost game
+const LOSS_REWARD: f32 = 0.0;
+
+pub(crate) fn is_win(rewards: &Rewards, player: &PlayerIndex) -> bool {
+    rewards.for_player(player) >= WIN_REWARD
+}
+
+pub(crate) fn is_loss(rewards: &Rewards, player: &PlayerIndex) -> bool {
+    rewards.for_player(player) <= LOSS_REWARD
+}
+
+/// Proves the node of a playout, if it is final, and then each ancestor whose value follows
+/// from its children. Safe to call with shared access while other threads search.
+pub(crate) fn prove<Rules: GameRules>(tree: &Tree<Rules>, id: NodeId) {
+    let mut current = Some(id);
+    while let Some(id) = current {
+        let node = tree.node(id);
+        if node.proven.get().is_none() {
+            let value = if node.is_final {
+                Some(node.state.reward())
+            } else {
+                proven_value(tree, id)
+            };
+            match value {
+                // Another thread may have proven the node in the meantime, with the same value
+                Some(value) => drop(node.proven.set(value)),
+                None => return,
+            }
+        }
+        current = node.parent;
+    }
+}
+
+/// Value of a node which is not final: That of a child which wins for the player to move, or of
+/// the best child once all children are proven
+fn proven_value<Rules: GameRules>(tree: &Tree<Rules>, id: NodeId) -> Option<Rewards> {
+    let player = tree.node(id).state.next_player();
+    let mut best: Option<&Rewards> = None;
+    let mut all_proven = tree.is_fully_expanded(id);
+    for edge in tree.children(id) {
+        match tree.node(edge.child.unwrap()).proven.get() {
+            Some(value) if is_win(value, &player) => return Some(value.clone()),
+            Some(value) => {
+                if best.is_none_or(|best| value.for_player(&player) > best.for_player(&player)) {
+                    best = Some(value);
+                }
+            }
+            None => all_proven = false,
+        }
+    }
+    if all_proven {
+        best.cloned()
+    } else {
+        None
+    }
+}
+
+/// Whether the search can stop, because the value of the node is known
+pub(crate) fn is_solved<Rules: GameRules>(tree: &Tree<Rules>, id: NodeId) -> bool {
+    tree.node(id).proven.get().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use game_ai::{GameRules, GameStateTrait};
+    use rand::seq::SliceRandom;
+    use tic_tac_toe::{TTTRules, TTTState};
+
+    use super::{is_solved, prove};
+    use crate::tree::{Tree, ROOT};
+
+    #[test]
+    fn winning_child_proves_parent() {
+        let mut tree = Tree::<TTTRules>::new(TTTState::winning_position());
+        loop {
+            let child = tree.expand_random_child(ROOT);
+            prove(&tree, child);
+            if tree.node(child).is_final {
+                break;
+            }
+            assert!(!is_solved(&tree, ROOT));
+        }
+        let winner = tree.node(ROOT).state.next_player();
+        assert_eq!(
+            tree.node(ROOT).proven.get().unwrap().for_player(&winner),
+            1.0
+        );
+    }
+
+    #[test]
+    fn fully_expanded_tree_is_solved() {
+        for _ in 0..20 {
+            let mut state = TTTState::default();
+            for _ in 0..4 {
+                let action = state
+                    .get_actions()
+                    .choose(&mut rand::thread_rng())
+                    .unwrap()
+                    .clone();
+                state = TTTRules::play(&state, &action);
+            }
+            let mut tree = Tree::<TTTRules>::new(state.clone());
+            let mut unexpanded = vec![ROOT];
+            while let Some(id) = unexpanded.pop() {
+                if tree.node(id).is_final {
+                    continue;
+                }
+                while !tree.is_fully_expanded(id) {
+                    let child = tree.expand_random_child(id);
+                    prove(&tree, child);
+                    unexpanded.push(child);
+                }
+            }
+            assert_eq!(tree.node(ROOT).proven.get(), Some(&state.solve()));
+        }
+    }
+}
@@ -0,0 +1,584 @@
+//! Search tree stored in contiguous arenas: Nodes refer to each other by their index, and the
+//! edges to the children of a node are a contiguous range of the edge arena.
+//! Node statistics are atomic, so that several threads can search the same tree.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards, MAX_PLAYERS};
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::prior::SharedMovePrior;
+
+pub(crate) type NodeId = u32;
+
+/// Every tree starts at the first node of the arena
+pub(crate) const ROOT: NodeId = 0;
+
+/// f32 which can be updated concurrently
+#[derive(Debug, Default)]
+struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn fetch_add(&self, value: f32) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some((f32::from_bits(current) + value).to_bits())
+            });
+    }
+
+    fn add_mut(&mut self, value: f32) {
+        let current = self.0.get_mut();
+        *current = (f32::from_bits(*current) + value).to_bits();
+    }
+}
+
+impl Clone for AtomicF32 {
+    fn clone(&self) -> Self {
+        AtomicF32(AtomicU32::new(self.0.load(Ordering::Relaxed)))
+    }
+}
+
+/// Playout statistics of a node
+#[derive(Debug, Default)]
+pub(crate) struct NodeStats {
+    playouts: AtomicU32,
+    /// Threads currently searching below this node. Each counts as a lost playout, so that other
+    /// threads prefer different paths.
+    virtual_losses: AtomicU32,
+    rewards: [AtomicF32; MAX_PLAYERS],
+    squared_rewards: [AtomicF32; MAX_PLAYERS],
+}
+
+impl NodeStats {
+    pub(crate) fn playouts(&self) -> f32 {
+        self.playouts.load(Ordering::Relaxed) as f32
+    }
+
+    /// Playouts including virtual losses
+    pub(crate) fn visits(&self) -> f32 {
+        (self.playouts.load(Ordering::Relaxed) + self.virtual_losses.load(Ordering::Relaxed)) as f32
+    }
+
+    /// Sum of the rewards of all playouts for `player`
+    pub(crate) fn reward(&self, player: &PlayerIndex) -> f32 {
+        self.rewards[player.index()].load()
+    }
+
+    /// Sum of the squared rewards of all playouts for `player`
+    pub(crate) fn squared_reward(&self, player: &PlayerIndex) -> f32 {
+        self.squared_rewards[player.index()].load()
+    }
+
+    fn add_playout(&mut self, result: &Rewards) {
+        *self.playouts.get_mut() += 1;
+        for index in 0..MAX_PLAYERS {
+            let reward = result.for_player(&PlayerIndex::new(index));
+            self.rewards[index].add_mut(reward);
+            self.squared_rewards[index].add_mut(reward * reward);
+        }
+    }
+
+    fn add_shared_playout(&self, result: &Rewards) {
+        self.playouts.fetch_add(1, Ordering::Relaxed);
+        for index in 0..MAX_PLAYERS {
+            let reward = result.for_player(&PlayerIndex::new(index));
+            self.rewards[index].fetch_add(reward);
+            self.squared_rewards[index].fetch_add(reward * reward);
+        }
+    }
+
+    fn merge(&mut self, other: &NodeStats) {
+        *self.playouts.get_mut() += other.playouts.load(Ordering::Relaxed);
+        for index in 0..MAX_PLAYERS {
+            self.rewards[index].add_mut(other.rewards[index].load());
+            self.squared_rewards[index].add_mut(other.squared_rewards[index].load());
+        }
+    }
+}
+
+impl Clone for NodeStats {
+    fn clone(&self) -> Self {
+        NodeStats {
+            playouts: AtomicU32::new(self.playouts.load(Ordering::Relaxed)),
+            virtual_losses: AtomicU32::new(self.virtual_losses.load(Ordering::Relaxed)),
+            rewards: std::array::from_fn(|index| self.rewards[index].clone()),
+            squared_rewards: std::array::from_fn(|index| self.squared_rewards[index].clone()),
+        }
+    }
+}
+
+/// All-moves-as-first statistics of an action, for RAVE: Playouts in which the player to move at
+/// the node played the action at any later point, and their rewards for that player
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AmafStats {
+    visits: AtomicF32,
+    reward: AtomicF32,
+}
+
+impl AmafStats {
+    pub(crate) fn visits(&self) -> f32 {
+        self.visits.load()
+    }
+
+    pub(crate) fn reward(&self) -> f32 {
+        self.reward.load()
+    }
+
+    pub(crate) fn add_playout(&self, reward: f32) {
+        self.visits.fetch_add(1.0);
+        self.reward.fetch_add(reward);
+    }
+
+    fn merge(&mut self, other: &AmafStats) {
+        self.visits.add_mut(other.visits());
+        self.reward.add_mut(other.reward());
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct EdgeRange {
+    start: u32,
+    len: u32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Edge<Action> {
+    pub(crate) action: Action,
+    /// Node after the action, if it was expanded already
+    pub(crate) child: Option<NodeId>,
+    /// Prior probability of the action
+    pub(crate) prior: f32,
+    pub(crate) amaf: AmafStats,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Node<State> {
+    pub(crate) state: State,
+    pub(crate) is_final: bool,
+    pub(crate) stats: NodeStats,
+    pub(crate) parent: Option<NodeId>,
+    /// Rewards of the node, once the solver has proven its game theoretic value
+    pub(crate) proven: OnceLock<Rewards>,
+    /// Edges for all actions, created on the first expansion
+    edges: Option<EdgeRange>,
+    /// The first `expanded` edges have a child node
+    expanded: u32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Tree<Rules: GameRules> {
+    nodes: Vec<Node<Rules::State>>,
+    edges: Vec<Edge<Rules::Action>>,
+}
+
+impl<Rules: GameRules> Default for Tree<Rules> {
+    fn default() -> Self {
+        Tree {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<Rules: GameRules> Tree<Rules> {
+    pub(crate) fn new(state: Rules::State) -> Tree<Rules> {
+        let mut tree = Tree::default();
+        tree.add_node(state, None);
+        tree
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of nodes in the tree
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(crate) fn node(&self, id: NodeId) -> &Node<Rules::State> {
+        &self.nodes[id as usize]
+    }
+
+    fn add_node(&mut self, state: Rules::State, parent: Option<NodeId>) -> NodeId {
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            is_final: state.is_final(),
+            state,
+            stats: NodeStats::default(),
+            parent,
+            proven: OnceLock::new(),
+            edges: None,
+            expanded: 0,
+        });
+        id
+    }
+
+    /// Edges of the expanded children of a node
+    pub(crate) fn children(&self, id: NodeId) -> &[Edge<Rules::Action>] {
+        let node = self.node(id);
+        match node.edges {
+            Some(range) => {
+                let start = range.start as usize;
+                &self.edges[start..start + node.expanded as usize]
+            }
+            None => &[],
+        }
+    }
+
+    /// Edges of all actions of a node, including those which have not been expanded
+    pub(crate) fn edges(&self, id: NodeId) -> &[Edge<Rules::Action>] {
+        match self.node(id).edges {
+            Some(range) => {
+                let start = range.start as usize;
+                &self.edges[start..start + range.len as usize]
+            }
+            None => &[],
+        }
+    }
+
+    /// Edges of all actions of a node, which are created if necessary
+    pub(crate) fn edges_mut(
+        &mut self,
+        id: NodeId,
+        move_prior: Option<&SharedMovePrior<Rules>>,
+    ) -> &mut [Edge<Rules::Action>] {
+        let range = self.edge_range(id, move_prior);
+        let start = range.start as usize;
+        &mut self.edges[start..start + range.len as usize]
+    }
+
+    /// Returns true if a child exists for every possible move
+    pub(crate) fn is_fully_expanded(&self, id: NodeId) -> bool {
+        let node = self.node(id);
+        node.edges.is_some_and(|range| node.expanded == range.len)
+    }
+
+    /// Edges for all actions of a node, which are created if necessary. Their priors are uniform
+    /// without a move prior.
+    fn edge_range(&mut self, id: NodeId, move_prior: Option<&SharedMovePrior<Rules>>) -> EdgeRange {
+        match self.node(id).edges {
+            Some(range) => range,
+            None => {
+                let state = &self.node(id).state;
+                let actions = state.get_actions();
+                let range = EdgeRange {
+                    start: self.edges.len() as u32,
+                    len: actions.len() as u32,
+                };
+                let priors = match move_prior {
+                    Some(move_prior) => move_prior.priors(state, &actions),
+                    None => vec![1.0 / actions.len() as f32; actions.len()],
+                };
+                self.edges
+                    .extend(actions.into_iter().zip(priors).map(|(action, prior)| Edge {
+                        action,
+                        child: None,
+                        prior,
+                        amaf: AmafStats::default(),
+                    }));
+                self.nodes[id as usize].edges = Some(range);
+                range
+            }
+        }
+    }
+
+    /// Adds the child of a random action, which has not been expanded yet
+    #[cfg(test)]
+    pub(crate) fn expand_random_child(&mut self, id: NodeId) -> NodeId {
+        self.expand_child(id, None)
+    }
+
+    /// Adds the child of the action with the highest prior, which has not been expanded yet.
+    /// Without a move prior, the action is chosen at random.
+    pub(crate) fn expand_child(
+        &mut self,
+        id: NodeId,
+        move_prior: Option<&SharedMovePrior<Rules>>,
+    ) -> NodeId {
+        let range = self.edge_range(id, move_prior);
+        let expanded = self.node(id).expanded;
+        assert!(expanded < range.len, "Node is fully expanded");
+        let unexpanded = (range.start + expanded) as usize..(range.start + range.len) as usize;
+        let mut rng = rand::thread_rng();
+        let chosen = match move_prior {
+            Some(_) => *unexpanded
+                .max_set_by(|index_1, index_2| {
+                    self.edges[*index_1]
+                        .prior
+                        .total_cmp(&self.edges[*index_2].prior)
+                })
+                // Random tiebreaker
+                .choose(&mut rng)
+                .unwrap(),
+            None => rng.gen_range(unexpanded),
+        };
+        self.expand_edge(id, chosen)
+    }
+
+    /// Adds the child of `action`, which has not been expanded yet
+    fn expand_action(&mut self, id: NodeId, action: &Rules::Action) -> NodeId {
+        let range = self.edge_range(id, None);
+        let first_unexpanded = (range.start + self.node(id).expanded) as usize;
+        let chosen = (first_unexpanded..(range.start + range.len) as usize)
+            .find(|index| self.edges[*index].action == *action)
+            .expect("Action is not an unexpanded move of the node");
+        self.expand_edge(id, chosen)
+    }
+
+    fn expand_edge(&mut self, id: NodeId, chosen: usize) -> NodeId {
+        // Move the chosen edge behind the expanded ones
+        let first_unexpanded =
+            (self.node(id).edges.unwrap().start + self.node(id).expanded) as usize;
+        self.edges.swap(first_unexpanded, chosen);
+
+        let state = Rules::play(&self.node(id).state, &self.edges[first_unexpanded].action);
+        let child = self.add_node(state, Some(id));
+        self.edges[first_unexpanded].child = Some(child);
+        self.nodes[id as usize].expanded += 1;
+        child
+    }
+
+    /// Adds the result of a playout to the node and all its ancestors
+    pub(crate) fn backup(&mut self, id: NodeId, result: &Rewards) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let node = &mut self.nodes[id as usize];
+            node.stats.add_playout(result);
+            current = node.parent;
+        }
+    }
+
+    /// Marks the node and all its ancestors as being searched by a thread
+    pub(crate) fn add_virtual_loss(&self, id: NodeId) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let node = self.node(id);
+            node.stats.virtual_losses.fetch_add(1, Ordering::Relaxed);
+            current = node.parent;
+        }
+    }
+
+    /// Replaces the virtual loss of a thread by the result of its playout
+    pub(crate) fn backup_shared(&self, id: NodeId, result: &Rewards) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let node = self.node(id);
+            node.stats.add_shared_playout(result);
+            node.stats.virtual_losses.fetch_sub(1, Ordering::Relaxed);
+            current = node.parent;
+        }
+    }
+
+    /// Adds the playouts of `other`, a search of the same state, to this tree. Nodes that were only
+    /// expanded in `other` are added, and proven values and AMAF statistics are kept.
+    pub(crate) fn merge(&mut self, other: &Tree<Rules>) {
+        // The same node in this tree and in `other`
+        let mut pending = vec![(ROOT, ROOT)];
+        while let Some((id, other_id)) = pending.pop() {
+            let other_node = other.node(other_id);
+            let node = &mut self.nodes[id as usize];
+            node.stats.merge(&other_node.stats);
+            if let Some(proven) = other_node.proven.get() {
+                let _ = node.proven.set(proven.clone());
+            }
+            if other_node.edges.is_none() {
+                continue;
+            }
+
+            let is_new = node.edges.is_none();
+            let edges = self.edges_mut(id, None);
+            for other_edge in other.edges(other_id) {
+                let edge = edges
+                    .iter_mut()
+                    .find(|edge| edge.action == other_edge.action)
+                    .expect("Both trees have the same actions");
+                if is_new {
+                    edge.prior = other_edge.prior;
+                }
+                edge.amaf.merge(&other_edge.amaf);
+            }
+            for other_edge in other.children(other_id) {
+                let existing_child = self
+                    .children(id)
+                    .iter()
+                    .find(|edge| edge.action == other_edge.action)
+                    .and_then(|edge| edge.child);
+                let child = match existing_child {
+                    Some(child) => child,
+                    None => self.expand_action(id, &other_edge.action),
+                };
+                pending.push((child, other_edge.child.unwrap()));
+            }
+        }
+    }
+
+    /// Copies the subtree below `new_root` into a new tree, discarding all other nodes
+    pub(crate) fn subtree(&self, new_root: NodeId) -> Tree<Rules> {
+        let mut tree = Tree::default();
+        // Nodes to copy, with their parent in the new tree. Their index in this list is their id
+        // in the new tree.
+        let mut copied: Vec<(NodeId, Option<NodeId>)> = vec![(new_root, None)];
+        let mut index = 0;
+        while index < copied.len() {
+            let (old_id, parent) = copied[index];
+            let old_node = self.node(old_id);
+            let edges = old_node.edges.map(|range| {
+                let new_range = EdgeRange {
+                    start: tree.edges.len() as u32,
+                    len: range.len,
+                };
+                let start = range.start as usize;
+                for edge in &self.edges[start..start + range.len as usize] {
+                    let child = edge.child.map(|old_child| {
+                        copied.push((old_child, Some(index as NodeId)));
+                        (copied.len() - 1) as NodeId
+                    });
+                    tree.edges.push(Edge {
+                        action: edge.action.clone(),
+                        child,
+                        prior: edge.prior,
+                        amaf: edge.amaf.clone(),
+                    });
+                }
+                new_range
+            });
+            tree.nodes.push(Node {
+                state: old_node.state.clone(),
+                is_final: old_node.is_final,
+                stats: old_node.stats.clone(),
+                parent,
+                proven: old_node.proven.clone(),
+                edges,
+                expanded: old_node.expanded,
+            });
+            index += 1;
+        }
+        tree
+    }
+
+    pub(crate) fn leaf_nodes_count(&self) -> usize {
+        self.nodes.iter().filter(|node| node.expanded == 0).count()
+    }
+
+    pub(crate) fn max_depth(&self, id: NodeId) -> usize {
+        1 + self
+            .children(id)
+            .iter()
+            .filter_map(|edge| edge.child)
+            .map(|child| self.max_depth(child))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use game_ai::{GameStateTrait, PlayerIndex, Rewards};
+    use tic_tac_toe::{TTTAction, TTTRules, TTTState};
+
+    use super::{Tree, ROOT};
+    use crate::prior::{MovePrior, SharedMovePrior};
+
+    /// Prefers actions which come first
+    struct Descending {}
+
+    impl MovePrior for Descending {
+        type Rules = TTTRules;
+
+        fn priors(&self, _state: &TTTState, actions: &[TTTAction]) -> Vec<f32> {
+            let sum = (actions.len() * (actions.len() + 1) / 2) as f32;
+            (1..=actions.len())
+                .rev()
+                .map(|weight| weight as f32 / sum)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn expand_and_copy_subtree() {
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        let child = tree.expand_random_child(ROOT);
+        let grandchild = tree.expand_random_child(child);
+        tree.expand_random_child(ROOT);
+        tree.backup(grandchild, &Rewards::new(&[1.0, 0.0]));
+
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.children(ROOT).len(), 2);
+        assert!(!tree.is_fully_expanded(ROOT));
+        assert_eq!(tree.node(ROOT).stats.playouts(), 1.0);
+        assert_eq!(tree.max_depth(ROOT), 3);
+
+        let subtree = tree.subtree(child);
+        assert_eq!(subtree.len(), 2);
+        assert_eq!(subtree.node(ROOT).parent, None);
+        assert_eq!(subtree.node(ROOT).state.get_actions().len(), 8);
+        let new_grandchild = subtree.children(ROOT)[0].child.unwrap();
+        assert_eq!(subtree.node(new_grandchild).parent, Some(ROOT));
+        let stats = &subtree.node(new_grandchild).stats;
+        assert_eq!(stats.reward(&PlayerIndex::ZERO), 1.0);
+        assert_eq!(stats.reward(&PlayerIndex::ONE), 0.0);
+    }
+
+    #[test]
+    fn merge_adds_playouts_of_all_nodes() {
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        let child = tree.expand_random_child(ROOT);
+        tree.backup(child, &Rewards::new(&[1.0, 0.0]));
+
+        let mut other = Tree::<TTTRules>::new(TTTState::default());
+        for _ in 0..3 {
+            let other_child = other.expand_random_child(ROOT);
+            other.backup(other_child, &Rewards::new(&[0.0, 1.0]));
+            let other_grandchild = other.expand_random_child(other_child);
+            other.backup(other_grandchild, &Rewards::new(&[0.0, 1.0]));
+            other.edges(ROOT)[0].amaf.add_playout(1.0);
+        }
+
+        tree.merge(&other);
+        assert_eq!(tree.node(ROOT).stats.playouts(), 7.0);
+        assert_eq!(tree.node(ROOT).stats.reward(&PlayerIndex::ONE), 6.0);
+        assert_eq!(
+            tree.len(),
+            other.len() + usize::from(tree.children(ROOT).len() == 4)
+        );
+        let amaf_visits: f32 = tree.edges(ROOT).iter().map(|edge| edge.amaf.visits()).sum();
+        assert_eq!(amaf_visits, 3.0);
+        // The grandchildren keep the playouts they had in `other`
+        let mut grandchildren_playouts = 0.0;
+        for edge in tree.children(ROOT) {
+            for grandchild_edge in tree.children(edge.child.unwrap()) {
+                grandchildren_playouts +=
+                    tree.node(grandchild_edge.child.unwrap()).stats.playouts();
+            }
+        }
+        assert_eq!(grandchildren_playouts, 3.0);
+    }
+
+    #[test]
+    fn children_are_expanded_in_order_of_prior() {
+        let move_prior: SharedMovePrior<TTTRules> = Arc::new(Descending {});
+        let mut tree = Tree::<TTTRules>::new(TTTState::default());
+        let actions = TTTState::default().get_actions();
+        for action in &actions {
+            tree.expand_child(ROOT, Some(&move_prior));
+            let edge = tree.children(ROOT).last().unwrap();
+            assert_eq!(edge.action, *action);
+        }
+        let priors = tree.children(ROOT).iter().map(|edge| edge.prior);
+        assert!(priors
+            .clone()
+            .zip(priors.skip(1))
+            .all(|(prior_1, prior_2)| prior_1 > prior_2));
+    }
+}
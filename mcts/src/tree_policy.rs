@@ -0,0 +1,164 @@
+//! Scores which decide the child to descend into during selection
+
+use rand_distr::{Beta, Distribution};
+
+/// Statistics of a child during selection, rewards are those of the player choosing the child
+#[derive(Clone, Debug)]
+pub struct ChildStatistics {
+    /// Playouts through the child, including virtual losses of other threads
+    pub visits: f32,
+    /// Sum of the playout rewards. With RAVE, the mean is blended with the all-moves-as-first
+    /// value.
+    pub reward: f32,
+    /// Sum of the squared playout rewards
+    pub squared_reward: f32,
+    /// Prior probability of the action, uniform unless the search has a prior
+    pub prior: f32,
+}
+
+impl ChildStatistics {
+    pub fn mean_reward(&self) -> f32 {
+        if self.visits > 0.0 {
+            self.reward / self.visits
+        } else {
+            0.0
+        }
+    }
+}
+
+/// How selection balances exploration and exploitation
+pub trait TreePolicy: std::fmt::Debug + Send + Sync {
+    /// Score of a child, the child with the highest score is selected
+    fn score(&self, child: &ChildStatistics, parent_visits: f32) -> f32;
+}
+
+/// Upper confidence bound of the mean reward
+#[derive(Clone, Debug)]
+pub struct Ucb1 {
+    pub c: f32,
+}
+
+impl Default for Ucb1 {
+    fn default() -> Self {
+        Ucb1 { c: 2.0f32.sqrt() }
+    }
+}
+
+impl TreePolicy for Ucb1 {
+    fn score(&self, child: &ChildStatistics, parent_visits: f32) -> f32 {
+        child.mean_reward() + self.c * (2.0 * parent_visits.ln() / child.visits).sqrt()
+    }
+}
+
+/// UCB1 with an exploration term bounded by the observed variance of the rewards, which
+/// explores less where the rewards hardly vary
+#[derive(Clone, Debug)]
+pub struct Ucb1Tuned {
+    pub c: f32,
+}
+
+impl Default for Ucb1Tuned {
+    fn default() -> Self {
+        Ucb1Tuned { c: 1.0 }
+    }
+}
+
+impl TreePolicy for Ucb1Tuned {
+    fn score(&self, child: &ChildStatistics, parent_visits: f32) -> f32 {
+        let mean = child.mean_reward();
+        let log_ratio = parent_visits.ln() / child.visits;
+        let variance_bound =
+            child.squared_reward / child.visits - mean * mean + (2.0 * log_ratio).sqrt();
+        mean + self.c * (log_ratio * variance_bound.min(0.25)).sqrt()
+    }
+}
+
+/// Predictor + UCB as in AlphaZero: The exploration term is weighted by the prior of the action
+#[derive(Clone, Debug)]
+pub struct Puct {
+    pub c: f32,
+}
+
+impl Default for Puct {
+    fn default() -> Self {
+        Puct { c: 1.5 }
+    }
+}
+
+impl TreePolicy for Puct {
+    fn score(&self, child: &ChildStatistics, parent_visits: f32) -> f32 {
+        child.mean_reward() + self.c * child.prior * parent_visits.sqrt() / (1.0 + child.visits)
+    }
+}
+
+/// Samples the mean reward from its Beta posterior, assuming rewards between 0 and 1
+#[derive(Clone, Debug, Default)]
+pub struct ThompsonSampling {}
+
+impl TreePolicy for ThompsonSampling {
+    fn score(&self, child: &ChildStatistics, _parent_visits: f32) -> f32 {
+        let wins = child.reward.max(0.0);
+        let losses = (child.visits - child.reward).max(0.0);
+        Beta::new(1.0 + wins, 1.0 + losses)
+            .unwrap()
+            .sample(&mut rand::thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChildStatistics, Puct, ThompsonSampling, TreePolicy, Ucb1, Ucb1Tuned};
+
+    fn child(visits: f32, reward: f32, squared_reward: f32, prior: f32) -> ChildStatistics {
+        ChildStatistics {
+            visits,
+            reward,
+            squared_reward,
+            prior,
+        }
+    }
+
+    #[test]
+    fn rarely_visited_children_are_explored() {
+        let often_visited = child(90.0, 54.0, 54.0, 0.5);
+        let rarely_visited = child(10.0, 5.0, 5.0, 0.5);
+        let policies: [&dyn TreePolicy; 3] =
+            [&Ucb1::default(), &Ucb1Tuned::default(), &Puct::default()];
+        for policy in policies {
+            assert!(
+                policy.score(&rarely_visited, 100.0) > policy.score(&often_visited, 100.0),
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn ucb1_tuned_explores_less_without_variance() {
+        // Same mean reward, once always 0.5 and once half wins and half losses
+        let constant = child(1000.0, 500.0, 250.0, 1.0);
+        let varying = child(1000.0, 500.0, 500.0, 1.0);
+        let policy = Ucb1Tuned::default();
+        assert!(policy.score(&constant, 2000.0) < policy.score(&varying, 2000.0));
+    }
+
+    #[test]
+    fn puct_prefers_actions_with_high_prior() {
+        let likely = child(10.0, 5.0, 5.0, 0.8);
+        let unlikely = child(10.0, 5.0, 5.0, 0.2);
+        let policy = Puct::default();
+        assert!(policy.score(&likely, 20.0) > policy.score(&unlikely, 20.0));
+    }
+
+    #[test]
+    fn thompson_sampling_samples_probabilities() {
+        let policy = ThompsonSampling {};
+        let mut winning_samples = 0.0;
+        for _ in 0..100 {
+            let sample = policy.score(&child(50.0, 45.0, 45.0, 1.0), 100.0);
+            assert!((0.0..=1.0).contains(&sample));
+            winning_samples += sample;
+        }
+        assert!(winning_samples / 100.0 > 0.7);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use game_ai::{AsyncGameAi, BackgroundAi, GameStateTrait};
+use hexxagon_lib::game::{rules::HexxagonRules, GameState};
+use mcts::{GenericMonteCarloTreeSearchAi, Parallelism, StopCondition};
+use tic_tac_toe::{TTTRules, TTTState};
+
+const LONG_SEARCH: StopCondition = StopCondition::Time(Duration::from_secs(60));
+
+#[test]
+fn search_finishes_in_background() {
+    let mut ai = BackgroundAi::new(GenericMonteCarloTreeSearchAi::<TTTRules>::new(
+        StopCondition::Iterations(100),
+    ));
+    let state = TTTState::default();
+    ai.start_search(&state);
+
+    let action = loop {
+        if let Some(action) = ai.poll_move() {
+            break action;
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    assert!(state.get_actions().contains(&action));
+    assert!(!ai.is_searching());
+    assert!(ai.ai().is_some());
+    assert_eq!(ai.progress().best_move, Some(action));
+}
+
+#[test]
+fn move_now_stops_search() {
+    for parallelism in [
+        Parallelism::Sequential,
+        Parallelism::SharedTree { threads: 2 },
+        Parallelism::Root { trees: 2 },
+        Parallelism::Leaf { rollouts: 2 },
+    ] {
+        let mut ai = BackgroundAi::new(
+            GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(LONG_SEARCH)
+                .with_parallelism(parallelism),
+        );
+        let state = GameState::default();
+        let start = Instant::now();
+        ai.start_search(&state);
+        thread::sleep(Duration::from_millis(300));
+        assert!(ai.poll_move().is_none());
+
+        let progress = ai.progress();
+        assert!(progress.playouts > 0);
+        assert!(progress.best_move.is_some());
+
+        let action = ai.move_now().unwrap();
+        assert!(state.get_actions().contains(&action));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
+
+#[test]
+fn cancelled_search_returns_ai() {
+    let mut ai = BackgroundAi::new(GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(
+        LONG_SEARCH,
+    ));
+    ai.start_search(&GameState::default());
+    assert!(ai.ai().is_none());
+
+    ai.cancel();
+    assert!(!ai.is_searching());
+    assert!(ai.ai().is_some());
+    assert!(ai.move_now().is_none());
+}
@@ -0,0 +1,56 @@
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use mcts::{FinalMovePolicy, GenericMonteCarloTreeSearchAi, Parallelism, StopCondition};
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 2000;
+
+fn assert_plays_winning_move(mut ai: GenericMonteCarloTreeSearchAi<TTTRules>) {
+    let state = TTTState::winning_position();
+    for _ in 0..10 {
+        let ai_move = ai.determine_next_move(&state);
+        assert!(
+            TTTRules::play(&state, &ai_move).is_final(),
+            "{} played {:?}",
+            ai.name(),
+            ai_move
+        );
+    }
+}
+
+#[test]
+fn every_policy_plays_winning_move() {
+    for policy in [
+        FinalMovePolicy::RobustChild,
+        FinalMovePolicy::MaxChild,
+        FinalMovePolicy::MaxRobustChild,
+        FinalMovePolicy::SecureChild { a: 1.0 },
+    ] {
+        assert_plays_winning_move(
+            GenericMonteCarloTreeSearchAi::new(StopCondition::Iterations(ITERATIONS))
+                .with_tree_reuse(false)
+                .with_final_move_policy(policy),
+        );
+    }
+}
+
+#[test]
+fn shared_tree_search_plays_winning_move() {
+    assert_plays_winning_move(
+        GenericMonteCarloTreeSearchAi::new(StopCondition::Iterations(ITERATIONS))
+            .with_tree_reuse(false)
+            .with_parallelism(Parallelism::SharedTree { threads: 4 }),
+    );
+}
+
+#[test]
+fn max_robust_child_search_stays_within_budget() {
+    for parallelism in [Parallelism::Sequential, Parallelism::Root { trees: 2 }] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_tree_reuse(false)
+                .with_final_move_policy(FinalMovePolicy::MaxRobustChild)
+                .with_parallelism(parallelism);
+        ai.determine_next_move(&TTTState::default());
+        assert!(ai.tree_playouts() <= ITERATIONS, "{}", ai.name());
+    }
+}
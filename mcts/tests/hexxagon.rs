@@ -10,3 +10,9 @@ fn test_hexxagon() {
         GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(mcts::StopCondition::Iterations(100));
     let _resulting_move = ai.determine_next_move(&initial_state);
 }
+
+#[test]
+fn ai_can_be_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GenericMonteCarloTreeSearchAi<HexxagonRules>>();
+}
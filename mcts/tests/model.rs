@@ -0,0 +1,129 @@
+use game_ai::{
+    GameAi, GameRules, GameStateTrait, LinearModel, PolicyValueModel, Rewards, TrainingSample,
+};
+use hexxagon_lib::{
+    ai::HexxagonFeatures,
+    game::{rules::HexxagonRules, GameState},
+};
+use mcts::{
+    DirichletNoise, FinalMovePolicy, GenericMonteCarloTreeSearchAi, Parallelism, StopCondition,
+};
+use tic_tac_toe::{TTTAction, TTTFeatures, TTTRules, TTTState};
+
+/// Evaluates every state as a draw and gives the first action a tiny prior
+struct NeglectFirstAction;
+
+impl PolicyValueModel for NeglectFirstAction {
+    type Rules = TTTRules;
+
+    fn policy(&self, _state: &TTTState, actions: &[TTTAction]) -> Vec<f32> {
+        let neglected = 0.001;
+        let others = (1.0 - neglected) / (actions.len() - 1) as f32;
+        (0..actions.len())
+            .map(|index| if index == 0 { neglected } else { others })
+            .collect()
+    }
+
+    fn value(&self, _state: &TTTState) -> Rewards {
+        Rewards::new(&[0.5, 0.5])
+    }
+}
+
+#[test]
+fn model_search_plays_winning_move() {
+    let state = TTTState::winning_position();
+    let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(500))
+        .with_policy_value_model(LinearModel::new(TTTFeatures {}));
+    let ai_move = ai.determine_next_move(&state);
+    assert!(TTTRules::play(&state, &ai_move).is_final());
+}
+
+#[test]
+fn model_search_does_not_expand_low_prior_move() {
+    let state = TTTState::default();
+    let neglected = state.get_actions()[0].clone();
+    let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(100))
+        .with_policy_value_model(NeglectFirstAction);
+    ai.determine_next_move(&state);
+    let policy = ai.root_policy();
+    assert!(!policy.is_empty());
+    assert!(policy.iter().all(|(action, _)| *action != neglected));
+}
+
+#[test]
+fn training_learns_sample() {
+    let state = TTTState::winning_position();
+    let winning_action = state
+        .get_actions()
+        .into_iter()
+        .find(|action| TTTRules::play(&state, action).is_final())
+        .unwrap();
+    let sample = TrainingSample::<TTTRules> {
+        state: state.clone(),
+        policy: vec![(winning_action.clone(), 1.0)],
+        rewards: TTTRules::play(&state, &winning_action).reward(),
+    };
+
+    let mut model = LinearModel::new(TTTFeatures {});
+    let initial_loss = model.train(&sample, 0.1);
+    for _ in 0..100 {
+        model.train(&sample, 0.1);
+    }
+    assert!(model.train(&sample, 0.0) < initial_loss / 2.0);
+
+    let actions = state.get_actions();
+    let policy = model.policy(&state, &actions);
+    let best = (0..actions.len())
+        .max_by(|index_1, index_2| policy[*index_1].total_cmp(&policy[*index_2]))
+        .unwrap();
+    assert_eq!(actions[best], winning_action);
+    let player = state.next_player();
+    assert!(model.value(&state).for_player(&player) > 0.75);
+}
+
+#[test]
+fn self_play_trains_model() {
+    let mut model = LinearModel::new(TTTFeatures {});
+    for _game in 0..3 {
+        let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(100))
+            .with_policy_value_model(model.clone())
+            .with_root_noise(DirichletNoise::default())
+            .with_final_move_policy(FinalMovePolicy::Sample { temperature: 1.0 });
+        let mut state = TTTState::default();
+        let mut positions = vec![];
+        while !state.is_final() {
+            let action = ai.determine_next_move(&state);
+            let policy = ai.root_policy();
+            let probabilities: f32 = policy.iter().map(|(_, probability)| probability).sum();
+            assert!((probabilities - 1.0).abs() < 1e-5);
+            positions.push((state.clone(), policy));
+            state = TTTRules::play(&state, &action);
+        }
+        for (position, policy) in positions {
+            let sample = TrainingSample::<TTTRules> {
+                state: position,
+                policy,
+                rewards: state.reward(),
+            };
+            assert!(model.train(&sample, 0.05).is_finite());
+        }
+    }
+}
+
+#[test]
+fn hexxagon_model_search_returns_legal_move() {
+    let state = GameState::default();
+    for parallelism in [
+        Parallelism::Sequential,
+        Parallelism::SharedTree { threads: 2 },
+    ] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(100))
+                .with_policy_value_model(LinearModel::new(HexxagonFeatures {}))
+                .with_root_noise(DirichletNoise::default())
+                .with_parallelism(parallelism);
+        let ai_move = ai.determine_next_move(&state);
+        assert!(state.get_actions().contains(&ai_move));
+        assert_eq!(ai.tree_playouts(), 100);
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use hexxagon_lib::game::rules::HexxagonRules;
+use mcts::{GenericMonteCarloTreeSearchAi, Parallelism, StopCondition};
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 2000;
+
+fn parallel_modes() -> [Parallelism; 3] {
+    [
+        Parallelism::SharedTree { threads: 4 },
+        Parallelism::Root { trees: 4 },
+        Parallelism::Leaf { rollouts: 4 },
+    ]
+}
+
+#[test]
+fn iterations_count_playouts_of_all_threads() {
+    for parallelism in parallel_modes() {
+        let mut ai = GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(
+            StopCondition::Iterations(ITERATIONS),
+        )
+        .with_parallelism(parallelism.clone());
+        ai.determine_next_move(&<HexxagonRules as GameRules>::State::default());
+        assert_eq!(ai.tree_playouts(), ITERATIONS, "{:?}", parallelism);
+    }
+}
+
+#[test]
+fn leaf_parallel_search_plays_exact_iterations() {
+    let mut ai = GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(
+        ITERATIONS + 1,
+    ))
+    .with_parallelism(Parallelism::Leaf { rollouts: 3 });
+    ai.determine_next_move(&<HexxagonRules as GameRules>::State::default());
+    assert_eq!(ai.tree_playouts(), ITERATIONS + 1);
+}
+
+#[test]
+fn parallelism_without_threads_is_rejected() {
+    for parallelism in [
+        Parallelism::SharedTree { threads: 0 },
+        Parallelism::Root { trees: 0 },
+        Parallelism::Leaf { rollouts: 0 },
+    ] {
+        let result = std::panic::catch_unwind(move || {
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_parallelism(parallelism)
+        });
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn shared_tree_expands_one_node_per_iteration() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_parallelism(Parallelism::SharedTree { threads: 4 });
+    ai.determine_next_move(&<HexxagonRules as GameRules>::State::default());
+    assert_eq!(ai.tree_size(), ITERATIONS + 1);
+}
+
+#[test]
+fn time_limited_search_returns_legal_move() {
+    let state = TTTState::default();
+    for parallelism in parallel_modes() {
+        let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Time(
+            Duration::from_millis(50),
+        ))
+        .with_parallelism(parallelism);
+        let ai_move = ai.determine_next_move(&state);
+        assert!(state.get_actions().contains(&ai_move));
+        assert!(ai.tree_playouts() > 0);
+    }
+}
+
+#[test]
+fn root_parallel_search_continues_reused_tree() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_parallelism(Parallelism::Root { trees: 4 });
+    let state = TTTState::default();
+    ai.determine_next_move(&state);
+    ai.determine_next_move(&state);
+    assert_eq!(ai.tree_playouts(), 2 * ITERATIONS);
+}
+
+#[test]
+fn name_contains_parallelism() {
+    let ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+        .with_parallelism(Parallelism::SharedTree { threads: 4 });
+    assert_eq!(
+        ai.name(),
+        "MCTS (Iterations(2000), SharedTree { threads: 4 })"
+    );
+}
@@ -0,0 +1,81 @@
+use std::{thread, time::Duration};
+
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use mcts::{GenericMonteCarloTreeSearchAi, StopCondition};
+use rand::seq::SliceRandom;
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 100;
+const PONDER_TIME: Duration = Duration::from_millis(100);
+
+fn random_reply(state: &TTTState) -> TTTState {
+    let reply = state
+        .get_actions()
+        .choose(&mut rand::thread_rng())
+        .unwrap()
+        .clone();
+    TTTRules::play(state, &reply)
+}
+
+#[test]
+fn pondering_is_reused_after_opponent_reply() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS));
+    let state = TTTState::default();
+    let ai_move = ai.determine_next_move(&state);
+    let state = TTTRules::play(&state, &ai_move);
+
+    ai.start_pondering(&state);
+    thread::sleep(PONDER_TIME);
+    ai.stop_pondering();
+    assert!(ai.tree_playouts() > ITERATIONS);
+
+    ai.determine_next_move(&random_reply(&state));
+    assert!(ai.tree_playouts() > ITERATIONS);
+}
+
+#[test]
+fn search_stops_pondering() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS));
+    let state = TTTState::default();
+    ai.start_pondering(&state);
+    thread::sleep(PONDER_TIME);
+
+    let state = random_reply(&state);
+    let action = ai.determine_next_move(&state);
+    assert!(state.get_actions().contains(&action));
+    assert!(ai.tree_playouts() > ITERATIONS);
+}
+
+#[test]
+fn pondering_requires_tree_reuse() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_tree_reuse(false);
+    let state = TTTState::default();
+    ai.start_pondering(&state);
+    thread::sleep(PONDER_TIME);
+
+    ai.determine_next_move(&random_reply(&state));
+    assert_eq!(ai.tree_playouts(), ITERATIONS);
+}
+
+#[test]
+fn statistics_are_empty_without_a_tree() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS));
+    let check_empty = |ai: &GenericMonteCarloTreeSearchAi<TTTRules>| {
+        assert_eq!(ai.tree_playouts(), 0);
+        assert_eq!(ai.tree_depth(), 0);
+        assert_eq!(ai.tree_branching_factor(), 0.0);
+        assert!(ai.root_policy().is_empty());
+        assert!(ai.proven_rewards().is_none());
+    };
+    check_empty(&ai);
+
+    // The pondering thread holds the tree
+    ai.start_pondering(&TTTState::default());
+    check_empty(&ai);
+    ai.stop_pondering();
+}
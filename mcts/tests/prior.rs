@@ -0,0 +1,68 @@
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use hexxagon_lib::{
+    ai::CaptureHeuristic,
+    game::{rules::HexxagonRules, GameState},
+};
+use mcts::{
+    GenericMonteCarloTreeSearchAi, HeuristicPrior, MovePrior, Parallelism, ProgressiveWidening,
+    Puct, StopCondition,
+};
+use tic_tac_toe::{TTTAction, TTTRules, TTTState};
+
+const ITERATIONS: usize = 2000;
+
+/// Prefers moves which end the game
+struct FinishingMoves {}
+
+impl MovePrior for FinishingMoves {
+    type Rules = TTTRules;
+
+    fn priors(&self, state: &TTTState, actions: &[TTTAction]) -> Vec<f32> {
+        let weights = actions
+            .iter()
+            .map(|action| {
+                if TTTRules::play(state, action).is_final() {
+                    10.0
+                } else {
+                    1.0
+                }
+            })
+            .collect::<Vec<f32>>();
+        let sum: f32 = weights.iter().sum();
+        weights.into_iter().map(|weight| weight / sum).collect()
+    }
+}
+
+#[test]
+fn custom_prior_guides_search() {
+    let state = TTTState::winning_position();
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_move_prior(FinishingMoves {})
+            .with_progressive_widening(ProgressiveWidening::default())
+            .with_progressive_bias(1.0)
+            .with_tree_policy(Puct::default());
+    let ai_move = ai.determine_next_move(&state);
+    assert!(TTTRules::play(&state, &ai_move).is_final());
+}
+
+#[test]
+fn heuristic_prior_works_with_parallel_search() {
+    let state = GameState::default();
+    for parallelism in [
+        Parallelism::Sequential,
+        Parallelism::SharedTree { threads: 2 },
+        Parallelism::Root { trees: 2 },
+        Parallelism::Leaf { rollouts: 2 },
+    ] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(200))
+                .with_move_prior(HeuristicPrior::new(CaptureHeuristic {}, 1.0))
+                .with_progressive_widening(ProgressiveWidening::default())
+                .with_progressive_bias(0.5)
+                .with_parallelism(parallelism);
+        let ai_move = ai.determine_next_move(&state);
+        assert!(state.get_actions().contains(&ai_move));
+        assert_eq!(ai.tree_playouts(), 200);
+    }
+}
@@ -0,0 +1,44 @@
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use hexxagon_lib::game::{rules::HexxagonRules, GameState};
+use mcts::{GenericMonteCarloTreeSearchAi, Parallelism, RaveSchedule, StopCondition};
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 2000;
+
+#[test]
+fn rave_plays_winning_move() {
+    let state = TTTState::winning_position();
+    for schedule in [
+        RaveSchedule::Equivalence { k: 100.0 },
+        RaveSchedule::MinimumMse { bias: 0.1 },
+    ] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_rave(schedule.clone());
+        let ai_move = ai.determine_next_move(&state);
+        assert!(
+            TTTRules::play(&state, &ai_move).is_final(),
+            "{:?} played {:?}",
+            schedule,
+            ai_move
+        );
+    }
+}
+
+#[test]
+fn rave_works_with_parallel_search() {
+    let state = GameState::default();
+    for parallelism in [
+        Parallelism::SharedTree { threads: 2 },
+        Parallelism::Root { trees: 2 },
+        Parallelism::Leaf { rollouts: 2 },
+    ] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(200))
+                .with_rave(RaveSchedule::Equivalence { k: 1000.0 })
+                .with_parallelism(parallelism);
+        let ai_move = ai.determine_next_move(&state);
+        assert!(state.get_actions().contains(&ai_move));
+        assert_eq!(ai.tree_playouts(), 200);
+    }
+}
@@ -0,0 +1,95 @@
+use game_ai::{GameAi, GameRules, GameStateTrait, PlayerIndex, Rewards};
+use hexxagon_lib::{
+    ai::{CaptureHeuristic, HexxagonEvaluator},
+    game::{rules::HexxagonRules, GameState},
+};
+use mcts::{
+    EpsilonGreedyRollout, GenericMonteCarloTreeSearchAi, HeuristicRollout, Playout, RolloutPolicy,
+    StopCondition, TruncatedRollout, UniformRollout,
+};
+use tic_tac_toe::{TTTEvaluator, TTTRules, TTTState};
+
+fn assert_valid_rewards(rewards: &Rewards) {
+    let reward_0 = rewards.for_player(&PlayerIndex::ZERO);
+    let reward_1 = rewards.for_player(&PlayerIndex::ONE);
+    assert!((0.0..=1.0).contains(&reward_0), "{:?}", rewards);
+    assert!((0.0..=1.0).contains(&reward_1), "{:?}", rewards);
+}
+
+#[test]
+fn truncated_rollout_evaluates_state() {
+    let policy = TruncatedRollout::new(
+        UniformRollout::<HexxagonRules>::default(),
+        HexxagonEvaluator::default(),
+        0,
+        3.0,
+    );
+    // Both players have the same number of pieces
+    assert_eq!(
+        policy.rollout(&GameState::default()),
+        Playout {
+            rewards: Rewards::new(&[0.5, 0.5]),
+            actions: vec![]
+        }
+    );
+
+    let policy = TruncatedRollout::new(
+        HeuristicRollout::new(CaptureHeuristic {}, 1.0),
+        HexxagonEvaluator::default(),
+        10,
+        3.0,
+    );
+    for _ in 0..10 {
+        let Playout { rewards, actions } = policy.rollout(&GameState::default());
+        assert!(actions.len() <= 10);
+        assert_valid_rewards(&rewards);
+        let sum = rewards.for_player(&PlayerIndex::ZERO) + rewards.for_player(&PlayerIndex::ONE);
+        assert!((sum - 1.0).abs() < 1e-5, "{:?}", rewards);
+    }
+}
+
+#[test]
+fn heuristic_rollout_plays_until_game_ends() {
+    let policy = HeuristicRollout::new(CaptureHeuristic {}, 1.0);
+    let Playout { rewards, actions } = policy.rollout(&GameState::default());
+    assert_valid_rewards(&rewards);
+
+    // Replaying the actions ends the game
+    let mut state = GameState::default();
+    for action in &actions {
+        assert!(!state.is_final());
+        state = HexxagonRules::play(&state, action);
+    }
+    assert!(state.is_final());
+}
+
+#[test]
+fn greedy_rollout_completes_row() {
+    let state = TTTState::winning_position();
+    let policy = EpsilonGreedyRollout::new(TTTEvaluator {}, 0.0);
+    let action = policy.choose_action(&state);
+    assert!(TTTRules::play(&state, &action).is_final());
+    assert_eq!(
+        policy.rollout(&state),
+        Playout {
+            rewards: Rewards::new(&[1.0, 0.0]),
+            actions: vec![action]
+        }
+    );
+}
+
+#[test]
+fn search_uses_rollout_policy() {
+    let state = GameState::default();
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<HexxagonRules>::new(StopCondition::Iterations(200))
+            .with_rollout_policy(TruncatedRollout::new(
+                EpsilonGreedyRollout::new(HexxagonEvaluator::default(), 0.2),
+                HexxagonEvaluator::default(),
+                4,
+                3.0,
+            ));
+    let ai_move = ai.determine_next_move(&state);
+    assert!(state.get_actions().contains(&ai_move));
+    assert_eq!(ai.tree_playouts(), 200);
+}
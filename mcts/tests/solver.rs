@@ -0,0 +1,88 @@
+use game_ai::{GameAi, GameRules, GameStateTrait, Rewards};
+use mcts::{GenericMonteCarloTreeSearchAi, Parallelism, StopCondition};
+use rand::seq::SliceRandom;
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 100_000;
+
+#[test]
+fn proven_win_is_played_at_once() {
+    let state = TTTState::winning_position();
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_solver(true);
+    let ai_move = ai.determine_next_move(&state);
+    assert!(TTTRules::play(&state, &ai_move).is_final());
+    // The search stops as soon as the winning move is expanded
+    assert!(ai.tree_playouts() <= state.get_actions().len());
+    assert_eq!(ai.proven_rewards(), Some(Rewards::new(&[1.0, 0.0])));
+}
+
+#[test]
+fn search_solves_endgame() {
+    for _ in 0..10 {
+        let mut state = TTTState::default();
+        for _ in 0..4 {
+            let action = state
+                .get_actions()
+                .choose(&mut rand::thread_rng())
+                .unwrap()
+                .clone();
+            state = TTTRules::play(&state, &action);
+        }
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_solver(true);
+        let ai_move = ai.determine_next_move(&state);
+        let value = state.solve();
+        assert_eq!(ai.proven_rewards(), Some(value.clone()));
+        assert!(ai.tree_playouts() < ITERATIONS);
+
+        let player = state.next_player();
+        let value_of_move = TTTRules::play(&state, &ai_move).solve();
+        assert_eq!(
+            value_of_move.for_player(&player),
+            value.for_player(&player),
+            "{:?} is not optimal in {:?}",
+            ai_move,
+            state
+        );
+    }
+}
+
+#[test]
+fn parallel_searches_prove_win() {
+    let state = TTTState::winning_position();
+    for parallelism in [
+        Parallelism::SharedTree { threads: 2 },
+        Parallelism::Root { trees: 2 },
+        Parallelism::Leaf { rollouts: 2 },
+    ] {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_solver(true)
+                .with_parallelism(parallelism.clone());
+        let ai_move = ai.determine_next_move(&state);
+        assert!(
+            TTTRules::play(&state, &ai_move).is_final(),
+            "{:?}",
+            parallelism
+        );
+        let winner = state.next_player();
+        assert_eq!(
+            ai.proven_rewards()
+                .map(|rewards| rewards.for_player(&winner)),
+            Some(1.0),
+            "{:?}",
+            parallelism
+        );
+    }
+}
+
+#[test]
+fn solver_is_disabled_by_default() {
+    let mut ai = GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(500));
+    ai.determine_next_move(&TTTState::winning_position());
+    assert_eq!(ai.tree_playouts(), 500);
+    assert_eq!(ai.proven_rewards(), None);
+}
@@ -46,10 +46,7 @@ fn test_generic_mcts_tic_tac_toe() {
     for _ in 0..100 {
         assert_ne!(
             play_against_random(),
-            Rewards {
-                player_0: 0.0,
-                player_1: 1.0
-            },
+            Rewards::new(&[0.0, 1.0]),
             "MCTS must not loose against random opponent in tic tac toe"
         )
     }
@@ -0,0 +1,64 @@
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use mcts::{
+    ChildStatistics, GenericMonteCarloTreeSearchAi, Puct, StopCondition, ThompsonSampling,
+    TreePolicy, Ucb1, Ucb1Tuned,
+};
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 2000;
+
+/// Tries every move once, then exploits the best mean reward
+#[derive(Debug)]
+struct Greedy {}
+
+impl TreePolicy for Greedy {
+    fn score(&self, child: &ChildStatistics, _parent_visits: f32) -> f32 {
+        child.mean_reward()
+    }
+}
+
+fn assert_plays_winning_move(tree_policy: impl TreePolicy + Clone + 'static) {
+    let state = TTTState::winning_position();
+    for _ in 0..10 {
+        let mut ai =
+            GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+                .with_tree_policy(tree_policy.clone());
+        let ai_move = ai.determine_next_move(&state);
+        assert!(
+            TTTRules::play(&state, &ai_move).is_final(),
+            "{:?} played {:?}",
+            tree_policy,
+            ai_move
+        );
+    }
+}
+
+#[test]
+fn ucb1_plays_winning_move() {
+    assert_plays_winning_move(Ucb1::default());
+}
+
+#[test]
+fn ucb1_tuned_plays_winning_move() {
+    assert_plays_winning_move(Ucb1Tuned::default());
+}
+
+#[test]
+fn puct_plays_winning_move() {
+    assert_plays_winning_move(Puct::default());
+}
+
+#[test]
+fn thompson_sampling_plays_winning_move() {
+    assert_plays_winning_move(ThompsonSampling {});
+}
+
+#[test]
+fn custom_policy_plays_winning_move() {
+    let state = TTTState::winning_position();
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_tree_policy(Greedy {});
+    let ai_move = ai.determine_next_move(&state);
+    assert!(TTTRules::play(&state, &ai_move).is_final());
+}
@@ -0,0 +1,52 @@
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use mcts::{GenericMonteCarloTreeSearchAi, StopCondition};
+use rand::seq::SliceRandom;
+use tic_tac_toe::{TTTRules, TTTState};
+
+const ITERATIONS: usize = 5000;
+
+#[test]
+fn subtree_is_reused_after_opponent_reply() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS));
+    let state = TTTState::default();
+    let ai_move = ai.determine_next_move(&state);
+
+    let state = TTTRules::play(&state, &ai_move);
+    let reply = state
+        .get_actions()
+        .choose(&mut rand::thread_rng())
+        .unwrap()
+        .clone();
+    let state = TTTRules::play(&state, &reply);
+    ai.determine_next_move(&state);
+
+    assert!(ai.tree_playouts() > ITERATIONS);
+}
+
+#[test]
+fn unknown_state_starts_fresh_tree() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS));
+    let mut state = TTTState::default();
+    ai.determine_next_move(&state);
+
+    // Four moves later, the state is too deep in the old tree
+    for _ in 0..4 {
+        let action = state.get_actions()[0].clone();
+        state = TTTRules::play(&state, &action);
+    }
+    ai.determine_next_move(&state);
+    assert_eq!(ai.tree_playouts(), ITERATIONS);
+}
+
+#[test]
+fn tree_reuse_can_be_disabled() {
+    let mut ai =
+        GenericMonteCarloTreeSearchAi::<TTTRules>::new(StopCondition::Iterations(ITERATIONS))
+            .with_tree_reuse(false);
+    let state = TTTState::default();
+    ai.determine_next_move(&state);
+    ai.determine_next_move(&state);
+    assert_eq!(ai.tree_playouts(), ITERATIONS);
+}
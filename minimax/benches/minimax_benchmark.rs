@@ -1,9 +1,13 @@
 use criterion::{
     criterion_group, criterion_main, AxisScale, BenchmarkId, Criterion, PlotConfiguration,
+    Throughput,
 };
 use game_ai::{GameAi, GameRules};
-use hexxagon_lib::{ai::HexxagonEvaluator, game::rules::HexxagonRules};
-use minimax::MiniMax;
+use hexxagon_lib::{
+    ai::{CaptureHeuristic, HexxagonEvaluator},
+    game::rules::HexxagonRules,
+};
+use minimax::{MiniMax, MoveOrdering, Parallelism};
 
 fn hexxagon_search(c: &mut Criterion) {
     let initial_state = <HexxagonRules as GameRules>::State::default();
@@ -16,7 +20,7 @@ fn hexxagon_search(c: &mut Criterion) {
     for depth in 1usize..=3 {
         group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
             b.iter(|| {
-                let mut ai = MiniMax::new(depth, HexxagonEvaluator {});
+                let mut ai = MiniMax::new(depth, HexxagonEvaluator::default());
                 ai.determine_next_move(&initial_state)
             });
         });
@@ -24,5 +28,73 @@ fn hexxagon_search(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, hexxagon_search);
+fn hexxagon_parallel_search(c: &mut Criterion) {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+
+    let mut group = c.benchmark_group("hexxagon_minimax_depth_3");
+
+    for parallelism in [
+        Parallelism::Sequential,
+        Parallelism::RootSplit,
+        Parallelism::YoungBrothersWait { plies: 1 },
+    ] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{:?}", parallelism)),
+            &parallelism,
+            |b, parallelism| {
+                b.iter(|| {
+                    let mut ai = MiniMax::new(3, HexxagonEvaluator::default())
+                        .with_parallelism(parallelism.clone());
+                    ai.determine_next_move(&initial_state)
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn hexxagon_move_ordering(c: &mut Criterion) {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+
+    let mut group = c.benchmark_group("hexxagon_minimax_ordering_depth_3");
+
+    let no_ordering = MoveOrdering {
+        transposition_move: false,
+        killer_moves: false,
+        history: false,
+    };
+    let configurations = [
+        (
+            "unordered",
+            MiniMax::new(3, HexxagonEvaluator::default()).with_move_ordering(no_ordering.clone()),
+        ),
+        (
+            "capture_heuristic",
+            MiniMax::new(3, HexxagonEvaluator::default())
+                .with_move_ordering(no_ordering.clone())
+                .with_action_heuristic(CaptureHeuristic {}),
+        ),
+        (
+            "all",
+            MiniMax::new(3, HexxagonEvaluator::default())
+                .with_action_heuristic(CaptureHeuristic {}),
+        ),
+    ];
+    for (name, ai) in configurations {
+        // Node counts are deterministic, so criterion reports the nodes searched per second
+        let report = ai.clone().search(&initial_state);
+        group.throughput(Throughput::Elements(report.nodes));
+        group.bench_function(name, |b| {
+            b.iter(|| ai.clone().determine_next_move(&initial_state));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    hexxagon_search,
+    hexxagon_parallel_search,
+    hexxagon_move_ordering
+);
 criterion_main!(benches);
@@ -1,118 +1,914 @@
-use game_ai::{Evaluator, GameAi, GameRules, GameStateTrait};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use game_ai::{
+    ActionHeuristic, Evaluator, GameAi, GameRules, GameStateTrait, PlayerIndex, Pondering,
+    SearchControl, SearchProgress, StopCondition,
+};
+use rayon::prelude::*;
+
+mod move_ordering;
+mod multi_player;
+mod parallel;
+mod transposition_table;
+
+use move_ordering::{MoveOrderer, SharedActionHeuristic};
+use parallel::AtomicF32;
+use transposition_table::{Bound, TranspositionTable};
+
+pub use move_ordering::MoveOrdering;
+pub use multi_player::MultiPlayerSearch;
+pub use transposition_table::ReplacementPolicy;
+
+/// How the search is distributed over the rayon thread pool
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parallelism {
+    /// Search everything on the calling thread
+    Sequential,
+    /// Search all moves at the root in parallel
+    RootSplit,
+    /// Search all moves at the root in parallel, and additionally split nodes up to `plies`
+    /// below the root: Their first child is searched alone, the younger siblings in parallel.
+    YoungBrothersWait { plies: usize },
+}
+
+#[derive(Clone, Debug)]
+enum SearchLimit {
+    Depth(usize),
+    /// Search with increasing depth, starting at depth 0, until the stop condition is reached.
+    /// Every iteration searches one ply deeper.
+    IterativeDeepening(StopCondition),
+}
+
+/// Result of a search, explaining why a move was chosen
+#[derive(Clone, Debug)]
+pub struct SearchReport<Action> {
+    pub best_move: Action,
+    /// Value of the best move for the player to move, as given by the evaluator
+    pub value: f32,
+    /// Moves both players are expected to play, starting with the best move
+    pub principal_variation: Vec<Action>,
+    /// Number of searched positions, including those of aborted iterations
+    pub nodes: u64,
+    /// Number of positions in which the remaining moves were skipped
+    pub cutoffs: u64,
+    /// Depth of the last completed search
+    pub depth: usize,
+    pub elapsed: Duration,
+}
 
-#[derive(Clone)]
 pub struct MiniMax<Eval: Evaluator + Clone> {
     evaluator: Eval,
-    depth: usize,
+    limit: SearchLimit,
+    parallelism: Parallelism,
+    transposition_table: Option<TranspositionTable<<Eval::Rules as GameRules>::Action>>,
+    move_ordering: MoveOrdering,
+    action_heuristic: Option<SharedActionHeuristic<Eval::Rules>>,
+    multi_player_search: MultiPlayerSearch,
+    quiescence_plies: usize,
+    /// Fills the transposition table in the background, which it owns meanwhile
+    pondering: Pondering<TranspositionTable<<Eval::Rules as GameRules>::Action>>,
+}
+
+impl<Eval: Evaluator + Clone> Clone for MiniMax<Eval> {
+    /// The clone does not ponder
+    fn clone(&self) -> Self {
+        MiniMax {
+            evaluator: self.evaluator.clone(),
+            limit: self.limit.clone(),
+            parallelism: self.parallelism.clone(),
+            transposition_table: self.transposition_table.clone(),
+            move_ordering: self.move_ordering.clone(),
+            action_heuristic: self.action_heuristic.clone(),
+            multi_player_search: self.multi_player_search,
+            quiescence_plies: self.quiescence_plies,
+            pondering: Pondering::default(),
+        }
+    }
 }
 
 impl<Eval: Evaluator + Clone> MiniMax<Eval> {
     pub fn new(depth: usize, evaluator: Eval) -> MiniMax<Eval> {
-        MiniMax { depth, evaluator }
+        MiniMax {
+            evaluator,
+            limit: SearchLimit::Depth(depth),
+            parallelism: Parallelism::Sequential,
+            transposition_table: None,
+            move_ordering: MoveOrdering::default(),
+            action_heuristic: None,
+            multi_player_search: MultiPlayerSearch::Paranoid,
+            quiescence_plies: 0,
+            pondering: Pondering::default(),
+        }
+    }
+
+    /// Search deeper and deeper until the stop condition is reached, and play the best move of
+    /// the last completed depth. `StopCondition::Iterations(n)` searches up to depth `n - 1`.
+    pub fn new_iterative_deepening(
+        stop_condition: StopCondition,
+        evaluator: Eval,
+    ) -> MiniMax<Eval> {
+        MiniMax {
+            evaluator,
+            limit: SearchLimit::IterativeDeepening(stop_condition),
+            parallelism: Parallelism::Sequential,
+            transposition_table: None,
+            move_ordering: MoveOrdering::default(),
+            action_heuristic: None,
+            multi_player_search: MultiPlayerSearch::Paranoid,
+            quiescence_plies: 0,
+            pondering: Pondering::default(),
+        }
+    }
+
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> MiniMax<Eval> {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Remember search results of positions, which are reached again through a different order
+    /// of moves, or in later searches. Requires the game to implement `GameStateTrait::hash_key`.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_limit` - Size of the table in bytes
+    /// * `policy` - Which entry to keep when two positions share a slot
+    pub fn with_transposition_table(
+        mut self,
+        memory_limit: usize,
+        policy: ReplacementPolicy,
+    ) -> MiniMax<Eval> {
+        self.transposition_table = Some(TranspositionTable::new(memory_limit, policy));
+        self
+    }
+
+    /// Search used for games with more than two players. It is always sequential and does not
+    /// use the transposition table.
+    pub fn with_multi_player_search(
+        mut self,
+        multi_player_search: MultiPlayerSearch,
+    ) -> MiniMax<Eval> {
+        self.multi_player_search = multi_player_search;
+        self
+    }
+
+    /// Continue searching noisy moves (see `Evaluator::is_noisy`) for up to `plies` beyond the
+    /// depth of the search, to avoid evaluating positions in the middle of an exchange.
+    /// Only used for two player games.
+    pub fn with_quiescence(mut self, plies: usize) -> MiniMax<Eval> {
+        self.quiescence_plies = plies;
+        self
+    }
+
+    pub fn with_move_ordering(mut self, move_ordering: MoveOrdering) -> MiniMax<Eval> {
+        self.move_ordering = move_ordering;
+        self
+    }
+
+    /// Search moves which the game considers promising first
+    pub fn with_action_heuristic(
+        mut self,
+        action_heuristic: impl ActionHeuristic<Rules = Eval::Rules> + Send + Sync + 'static,
+    ) -> MiniMax<Eval> {
+        self.action_heuristic = Some(Arc::new(action_heuristic));
+        self
+    }
+
+    /// Takes back the transposition table from the background search, if there is one
+    fn finish_pondering(&mut self) {
+        if let Some(transposition_table) = self.pondering.stop() {
+            self.transposition_table = Some(transposition_table);
+        }
     }
 }
 
-impl<Eval: Evaluator + Clone> GameAi<Eval::Rules> for MiniMax<Eval> {
-    fn determine_next_move(
-        &mut self,
+/// State shared by all nodes of a single search
+struct Search<'a, Eval: Evaluator> {
+    eval: &'a Eval,
+    transposition_table: Option<&'a TranspositionTable<<Eval::Rules as GameRules>::Action>>,
+    move_orderer: &'a MoveOrderer<Eval::Rules>,
+    /// Depth of the nodes after the root moves
+    depth: usize,
+    /// Maximum number of noisy moves searched beyond the depth
+    quiescence_plies: usize,
+    deadline: Option<Instant>,
+    /// Aborts the search once it is set, like the deadline
+    stop: Option<&'a AtomicBool>,
+    aborted: AtomicBool,
+    nodes: AtomicU64,
+    cutoffs: AtomicU64,
+    /// Positions evaluated at the depth limit, although the game goes on. Zero means the search
+    /// solved the position, and deeper searches would not change its result.
+    horizon_nodes: AtomicU64,
+}
+
+impl<'a, Eval: Evaluator> Search<'a, Eval> {
+    /// Number of moves between the root and a node with the given remaining depth
+    fn ply(&self, depth: usize) -> usize {
+        self.depth - depth
+    }
+
+    /// Key of a node in the transposition table, if there is one.
+    /// A state can be searched for either player, which are stored separately.
+    fn transposition_key(
+        &self,
+        state: &<Eval::Rules as GameRules>::State,
+        maximizing_player: bool,
+    ) -> Option<u64> {
+        self.transposition_table?;
+        let key = state.hash_key()?;
+        Some(if maximizing_player { key } else { !key })
+    }
+
+    /// Checks the deadline and the stop flag. Once either is reached, nodes are no longer
+    /// expanded and the result of the search is meaningless.
+    fn should_abort(&self) -> bool {
+        if self.aborted.load(Ordering::Relaxed) {
+            return true;
+        }
+        let deadline_passed = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        let stopped = self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed));
+        if deadline_passed || stopped {
+            self.aborted.store(true, Ordering::Relaxed);
+        }
+        deadline_passed || stopped
+    }
+
+    fn was_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+}
+
+/// Depth of transposition table entries, whose subtree did not reach the depth limit, so that
+/// their value holds for searches of any depth
+const SOLVED_DEPTH: usize = usize::MAX;
+
+/// Value of a node, and the moves both players are expected to play from it
+pub(crate) struct NodeValue<Action> {
+    pub(crate) value: f32,
+    pub(crate) principal_variation: Vec<Action>,
+}
+
+impl<Action> NodeValue<Action> {
+    pub(crate) fn leaf(value: f32) -> NodeValue<Action> {
+        NodeValue {
+            value,
+            principal_variation: Vec::new(),
+        }
+    }
+}
+
+/// Value of a move at the root, and the bound of the search window it was searched with.
+/// Unless the value improves on the bound, it only is a bound on the true value.
+struct RootValue<Action> {
+    value: NodeValue<Action>,
+    bound: f32,
+}
+
+/// Bound of a search window that is open on the side of the given player
+fn unbounded(maximizing_player: bool) -> f32 {
+    if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
+    }
+}
+
+fn improves(maximizing_player: bool, value: f32, bound: f32) -> bool {
+    if maximizing_player {
+        value > bound
+    } else {
+        value < bound
+    }
+}
+
+impl<Eval> MiniMax<Eval>
+where
+    Eval: Evaluator + Clone + Sync,
+    <Eval::Rules as GameRules>::State: Sync,
+    <Eval::Rules as GameRules>::Action: Send + Sync,
+{
+    fn split_plies(&self) -> usize {
+        match self.parallelism {
+            Parallelism::Sequential | Parallelism::RootSplit => 0,
+            Parallelism::YoungBrothersWait { plies } => plies,
+        }
+    }
+
+    /// Searches the state after a root move, with a window that is only bounded on the side of
+    /// the player at the root
+    fn root_move_value(
+        &self,
         gamestate: &<Eval::Rules as GameRules>::State,
-    ) -> <Eval::Rules as GameRules>::Action {
-        let possible_moves = gamestate.get_actions();
-        let mut moves_values = vec![];
+        action: &<Eval::Rules as GameRules>::Action,
+        bound: f32,
+        search: &Search<Eval>,
+    ) -> NodeValue<<Eval::Rules as GameRules>::Action> {
+        let maximizing_player = gamestate.next_player().is_maximizing();
+        let (alpha, beta) = if maximizing_player {
+            (bound, f32::INFINITY)
+        } else {
+            (f32::NEG_INFINITY, bound)
+        };
+        let next_state = Eval::Rules::play(gamestate, action);
+        let value = minimax_value(
+            &next_state,
+            search.depth,
+            alpha,
+            beta,
+            !maximizing_player,
+            search,
+            self.split_plies(),
+        );
+        assert!(value.value.is_finite());
+        let mut principal_variation = vec![action.clone()];
+        principal_variation.extend(value.principal_variation);
+        NodeValue {
+            value: value.value,
+            principal_variation,
+        }
+    }
+
+    fn root_values(
+        &self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+        possible_moves: &[<Eval::Rules as GameRules>::Action],
+        search: &Search<Eval>,
+    ) -> Vec<RootValue<<Eval::Rules as GameRules>::Action>> {
+        let maximizing_player = gamestate.next_player().is_maximizing();
+        match self.parallelism {
+            Parallelism::Sequential => {
+                let mut bound = unbounded(maximizing_player);
+                possible_moves
+                    .iter()
+                    .map(|action| {
+                        let value = self.root_move_value(gamestate, action, bound, search);
+                        let root_value = RootValue { value, bound };
+                        if improves(maximizing_player, root_value.value.value, bound) {
+                            bound = root_value.value.value;
+                        }
+                        root_value
+                    })
+                    .collect()
+            }
+            Parallelism::RootSplit | Parallelism::YoungBrothersWait { .. } => {
+                let shared_bound = AtomicF32::new(unbounded(maximizing_player));
+                possible_moves
+                    .par_iter()
+                    .map(|action| {
+                        let bound = shared_bound.load();
+                        let value = self.root_move_value(gamestate, action, bound, search);
+                        if maximizing_player {
+                            shared_bound.fetch_max(value.value);
+                        } else {
+                            shared_bound.fetch_min(value.value);
+                        }
+                        RootValue { value, bound }
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn new_search<'a>(
+        &'a self,
+        move_orderer: &'a MoveOrderer<Eval::Rules>,
+        depth: usize,
+        deadline: Option<Instant>,
+        stop: Option<&'a AtomicBool>,
+    ) -> Search<'a, Eval> {
+        Search {
+            eval: &self.evaluator,
+            transposition_table: self.transposition_table.as_ref(),
+            move_orderer,
+            depth,
+            quiescence_plies: self.quiescence_plies,
+            deadline,
+            stop,
+            aborted: AtomicBool::new(false),
+            nodes: AtomicU64::new(0),
+            cutoffs: AtomicU64::new(0),
+            horizon_nodes: AtomicU64::new(0),
+        }
+    }
 
-        for action in possible_moves {
+    /// Searches all moves of a game with more than two players, and returns the value and
+    /// principal variation of the first move which is best for the player to move.
+    fn search_root_multi_player(
+        &self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+        search: &Search<Eval>,
+    ) -> Option<NodeValue<<Eval::Rules as GameRules>::Action>> {
+        let player = gamestate.next_player();
+        let mut best: Option<NodeValue<_>> = None;
+        for action in gamestate.get_actions() {
             let next_state = Eval::Rules::play(gamestate, &action);
-            moves_values.push((
-                action,
-                minimax_value(
-                    &next_state,
-                    self.depth,
-                    f32::NEG_INFINITY,
-                    f32::INFINITY,
-                    gamestate.next_player().is_maximizing(),
-                    &self.evaluator,
-                ),
-            ));
-            assert!(moves_values.last().unwrap().1.is_finite());
-        }
-
-        if gamestate.next_player().is_maximizing() {
-            moves_values
-                .iter()
-                .max_by(|(_action1, value1), (_action2, value2)| value1.total_cmp(value2))
-                .unwrap()
-                .0
-                .clone()
+            let bound = best.as_ref().map_or(f32::NEG_INFINITY, |best| best.value);
+            let (value, child_variation) = match self.multi_player_search {
+                MultiPlayerSearch::MaxN => {
+                    let value = multi_player::max_n_value(&next_state, search.depth, search);
+                    (value.values.for_player(&player), value.principal_variation)
+                }
+                MultiPlayerSearch::Paranoid => {
+                    let value = multi_player::paranoid_value(
+                        &next_state,
+                        search.depth,
+                        bound,
+                        f32::INFINITY,
+                        &player,
+                        search,
+                    );
+                    (value.value, value.principal_variation)
+                }
+            };
+            if search.was_aborted() {
+                return None;
+            }
+            if best.is_none() || value > bound {
+                let mut principal_variation = vec![action];
+                principal_variation.extend(child_variation);
+                best = Some(NodeValue {
+                    value,
+                    principal_variation,
+                });
+            }
+        }
+        best
+    }
+
+    /// Searches all moves to the depth of the search, and returns the value and principal
+    /// variation of the best one, unless the search was aborted.
+    fn search_root(
+        &self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+        search: &Search<Eval>,
+    ) -> Option<NodeValue<<Eval::Rules as GameRules>::Action>> {
+        let possible_moves = gamestate.get_actions();
+        let maximizing_player = gamestate.next_player().is_maximizing();
+        let moves_values = self.root_values(gamestate, &possible_moves, search);
+        if search.was_aborted() {
+            return None;
+        }
+
+        let is_exact = |v: &RootValue<_>| improves(maximizing_player, v.value.value, v.bound);
+        let exact_values = moves_values
+            .iter()
+            .filter(|v| is_exact(v))
+            .map(|v| v.value.value);
+        let best_value = if maximizing_player {
+            exact_values.max_by(f32::total_cmp)
         } else {
-            moves_values
-                .iter()
-                .min_by(|(_action1, value1), (_action2, value2)| value1.total_cmp(value2))
-                .unwrap()
-                .0
-                .clone()
+            exact_values.min_by(f32::total_cmp)
         }
+        .unwrap();
+
+        // Choose the first move reaching the best value, independent of the order in which the
+        // moves were searched. Moves which were cut off at exactly the best value might be tied
+        // with it, which is only known after searching them again without bound.
+        for (action, root_value) in possible_moves.iter().zip(moves_values) {
+            if root_value.value.value != best_value {
+                continue;
+            }
+            if is_exact(&root_value) {
+                return Some(root_value.value);
+            }
+            let value =
+                self.root_move_value(gamestate, action, unbounded(maximizing_player), search);
+            if search.was_aborted() {
+                return None;
+            }
+            if value.value == best_value {
+                return Some(value);
+            }
+        }
+        unreachable!("Best value has to be reached by at least one move");
+    }
+
+    /// Determines the best move like `determine_next_move`, and reports how it was found.
+    /// Stops pondering first.
+    pub fn search(
+        &mut self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+    ) -> SearchReport<<Eval::Rules as GameRules>::Action> {
+        self.finish_pondering();
+        self.search_until(gamestate, None, |_| {})
+    }
+
+    /// Converts a value of the root, which two player searches give for player zero
+    fn value_for_player_to_move(gamestate: &<Eval::Rules as GameRules>::State, value: f32) -> f32 {
+        if Eval::Rules::N_PLAYERS > 2 || gamestate.next_player().is_maximizing() {
+            value
+        } else {
+            -value
+        }
+    }
+
+    /// Searches until the limit is reached, the position is solved or `stop` is set, but at least
+    /// to the first depth. A fixed depth search which can be stopped searches depth 0 first, to
+    /// have a move to play right away. The progress is reported after every completed depth.
+    fn search_until(
+        &mut self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+        stop: Option<&AtomicBool>,
+        report: impl Fn(SearchProgress<<Eval::Rules as GameRules>::Action>),
+    ) -> SearchReport<<Eval::Rules as GameRules>::Action> {
+        let start = Instant::now();
+        if let Some(transposition_table) = &mut self.transposition_table {
+            transposition_table.new_search();
+        }
+        let move_orderer =
+            MoveOrderer::new(self.move_ordering.clone(), self.action_heuristic.clone());
+
+        let (depths, deadline): (Box<dyn Iterator<Item = usize>>, _) = match &self.limit {
+            SearchLimit::Depth(depth) if stop.is_some() && *depth > 0 => {
+                (Box::new([0, *depth].into_iter()), None)
+            }
+            SearchLimit::Depth(depth) => (Box::new(std::iter::once(*depth)), None),
+            SearchLimit::IterativeDeepening(StopCondition::Iterations(iterations)) => {
+                (Box::new(0..(*iterations).max(1)), None)
+            }
+            SearchLimit::IterativeDeepening(StopCondition::Time(duration)) => {
+                (Box::new(0..usize::MAX), Some(start + *duration))
+            }
+        };
+
+        let (mut nodes, mut cutoffs) = (0, 0);
+        let mut best = None;
+        for depth in depths {
+            // The first depth is always searched completely, to have a move to play
+            let deadline = deadline.filter(|_| best.is_some());
+            let stop = stop.filter(|_| best.is_some());
+            let search = self.new_search(&move_orderer, depth, deadline, stop);
+            let result = if Eval::Rules::N_PLAYERS > 2 {
+                self.search_root_multi_player(gamestate, &search)
+            } else {
+                self.search_root(gamestate, &search)
+            };
+            nodes += search.nodes.into_inner();
+            cutoffs += search.cutoffs.into_inner();
+            let Some(value) = result else {
+                break;
+            };
+            report(SearchProgress {
+                best_move: Some(value.principal_variation[0].clone()),
+                depth,
+                nodes,
+                playouts: 0,
+                value: Some(Self::value_for_player_to_move(gamestate, value.value)),
+                principal_variation: value.principal_variation.clone(),
+            });
+            best = Some((depth, value));
+            if search.horizon_nodes.into_inner() == 0 {
+                break;
+            }
+        }
+
+        let (depth, best) = best.unwrap();
+        SearchReport {
+            best_move: best.principal_variation[0].clone(),
+            value: Self::value_for_player_to_move(gamestate, best.value),
+            principal_variation: best.principal_variation,
+            nodes,
+            cutoffs,
+            depth,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+impl<Eval> GameAi<Eval::Rules> for MiniMax<Eval>
+where
+    Eval: Evaluator + Clone + Send + Sync + 'static,
+    <Eval::Rules as GameRules>::State: Send + Sync,
+    <Eval::Rules as GameRules>::Action: Send + Sync,
+{
+    fn determine_next_move(
+        &mut self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+    ) -> <Eval::Rules as GameRules>::Action {
+        self.search(gamestate).best_move
     }
 
     fn name(&self) -> String {
-        format!("MiniMax (depth {})", self.depth)
+        let mut limit = match &self.limit {
+            SearchLimit::Depth(depth) => format!("depth {}", depth),
+            SearchLimit::IterativeDeepening(stop_condition) => {
+                format!("iterative deepening, {:?}", stop_condition)
+            }
+        };
+        if self.quiescence_plies > 0 && Eval::Rules::N_PLAYERS == 2 {
+            limit += &format!(", quiescence {}", self.quiescence_plies);
+        }
+        if Eval::Rules::N_PLAYERS > 2 {
+            return format!("MiniMax ({}, {:?})", limit, self.multi_player_search);
+        }
+        match self.parallelism {
+            Parallelism::Sequential => format!("MiniMax ({})", limit),
+            _ => format!("MiniMax ({}, {:?})", limit, self.parallelism),
+        }
+    }
+
+    /// Stops after the current depth of iterative deepening, and plays the best move of the last
+    /// completed depth
+    fn determine_next_move_with_control(
+        &mut self,
+        gamestate: &<Eval::Rules as GameRules>::State,
+        control: &SearchControl<<Eval::Rules as GameRules>::Action>,
+    ) -> <Eval::Rules as GameRules>::Action {
+        self.finish_pondering();
+        self.search_until(gamestate, Some(control.stop_flag()), |progress| {
+            control.report(progress)
+        })
+        .best_move
+    }
+
+    /// Searches `gamestate` deeper and deeper to fill the transposition table, whose entries the
+    /// next search finds below the opponent's move. Requires a transposition table and two
+    /// players.
+    fn start_pondering(&mut self, gamestate: &<Eval::Rules as GameRules>::State) {
+        self.finish_pondering();
+        if Eval::Rules::N_PLAYERS > 2 || gamestate.is_final() {
+            return;
+        }
+        let Some(transposition_table) = self.transposition_table.take() else {
+            return;
+        };
+        let mut ponder = MiniMax {
+            limit: SearchLimit::IterativeDeepening(StopCondition::Iterations(usize::MAX)),
+            transposition_table: Some(transposition_table),
+            ..self.clone()
+        };
+        let gamestate = gamestate.clone();
+        self.pondering.start(move |stop| {
+            ponder.search_until(&gamestate, Some(stop), |_| {});
+            ponder.transposition_table.take().unwrap()
+        });
+    }
+
+    fn stop_pondering(&mut self) {
+        self.finish_pondering();
     }
 }
 
-fn minimax_value<Rules: GameRules, Eval: Evaluator<Rules = Rules>>(
+fn minimax_value<Rules: GameRules, Eval: Evaluator<Rules = Rules> + Sync>(
     state: &Rules::State,
     depth: usize,
     mut alpha: f32, // minimum score that the maximizing player is assured of
     mut beta: f32,  // maximum score that the minimizing player is assured of
     maximizing_player: bool,
-    eval: &Eval,
-) -> f32 {
-    if depth == 0 || state.is_final() {
-        return eval.value(state);
+    search: &Search<Eval>,
+    split_plies: usize, // remaining plies in which nodes are searched in parallel
+) -> NodeValue<Rules::Action>
+where
+    Rules::State: Sync,
+    Rules::Action: Send + Sync,
+{
+    search.nodes.fetch_add(1, Ordering::Relaxed);
+    if state.is_final() || search.should_abort() {
+        return NodeValue::leaf(search.eval.value(state, &PlayerIndex::ZERO));
+    }
+    if depth == 0 {
+        search.horizon_nodes.fetch_add(1, Ordering::Relaxed);
+        return quiescence_value(
+            state,
+            alpha,
+            beta,
+            maximizing_player,
+            search,
+            search.quiescence_plies,
+        );
     }
 
-    if maximizing_player {
-        let mut value = std::f32::NEG_INFINITY;
-        let possible_moves = state.get_actions();
-        for action in possible_moves {
-            let child_state = Rules::play(state, &action);
-            value = value.max(minimax_value(
-                &child_state,
+    let transposition_key = search.transposition_key(state, maximizing_player);
+    let stored = transposition_key.and_then(|key| search.transposition_table.unwrap().probe(key));
+    if let Some(stored) = &stored {
+        if stored.depth >= depth {
+            if stored.depth != SOLVED_DEPTH {
+                search.horizon_nodes.fetch_add(1, Ordering::Relaxed);
+            }
+            match stored.bound {
+                Bound::Exact => {}
+                Bound::Lower => alpha = alpha.max(stored.value),
+                Bound::Upper => beta = beta.min(stored.value),
+            }
+            if stored.bound == Bound::Exact || alpha >= beta {
+                return NodeValue {
+                    value: stored.value,
+                    principal_variation: stored.best_move.iter().cloned().collect(),
+                };
+            }
+        }
+    }
+    let (original_alpha, original_beta) = (alpha, beta);
+    // Parallel searches of other nodes may add to the count, which only keeps the value of this
+    // node from being stored as solved
+    let horizon_nodes = search.horizon_nodes.load(Ordering::Relaxed);
+
+    let ply = search.ply(depth);
+    let mut possible_moves = state.get_actions();
+    search.move_orderer.order(
+        state,
+        &mut possible_moves,
+        stored.and_then(|stored| stored.best_move).as_ref(),
+        ply,
+    );
+
+    let (best_child, best_index) = if maximizing_player {
+        let child_value = |child_state: &Rules::State, alpha: f32, beta: f32| {
+            minimax_value(
+                child_state,
                 depth - 1,
                 alpha,
                 beta,
                 false,
-                eval,
-            ));
-            alpha = alpha.max(value);
-            if value >= beta {
-                // Other children would only increase this nodes value,
-                // but one of our siblings has a lower value already,
-                // which would be chosen by the parent state
-                break;
+                search,
+                split_plies.saturating_sub(1),
+            )
+        };
+        if split_plies > 0 {
+            parallel::maximize_young_brothers_wait::<Rules>(
+                state,
+                &possible_moves,
+                alpha,
+                beta,
+                child_value,
+            )
+        } else {
+            let mut best_child = NodeValue::leaf(f32::NEG_INFINITY);
+            let mut best_index = None;
+            for (index, action) in possible_moves.iter().enumerate() {
+                let child_state = Rules::play(state, action);
+                let child_value = child_value(&child_state, alpha, beta);
+                if child_value.value > best_child.value {
+                    best_child = child_value;
+                    best_index = Some(index);
+                }
+                alpha = alpha.max(best_child.value);
+                if best_child.value >= beta {
+                    // Other children would only increase this nodes value,
+                    // but one of our siblings has a lower value already,
+                    // which would be chosen by the parent state
+                    break;
+                }
             }
+            (best_child, best_index)
         }
-        value
     } else {
-        let mut value = std::f32::INFINITY;
-        let possible_moves = state.get_actions();
-        for action in possible_moves {
-            let child_state = Rules::play(state, &action);
-            value = value.min(minimax_value(
-                &child_state,
+        let child_value = |child_state: &Rules::State, alpha: f32, beta: f32| {
+            minimax_value(
+                child_state,
                 depth - 1,
                 alpha,
                 beta,
-                false,
-                eval,
-            ));
-            beta = beta.min(value);
-            if value <= alpha {
-                // Other children would only reduce this nodes value,
-                // but one of our siblings has a higher value already,
-                // which would be chosen by the parent state
-                break;
+                true,
+                search,
+                split_plies.saturating_sub(1),
+            )
+        };
+        if split_plies > 0 {
+            parallel::minimize_young_brothers_wait::<Rules>(
+                state,
+                &possible_moves,
+                alpha,
+                beta,
+                child_value,
+            )
+        } else {
+            let mut best_child = NodeValue::leaf(f32::INFINITY);
+            let mut best_index = None;
+            for (index, action) in possible_moves.iter().enumerate() {
+                let child_state = Rules::play(state, action);
+                let child_value = child_value(&child_state, alpha, beta);
+                if child_value.value < best_child.value {
+                    best_child = child_value;
+                    best_index = Some(index);
+                }
+                beta = beta.min(best_child.value);
+                if best_child.value <= alpha {
+                    // Other children would only reduce this nodes value,
+                    // but one of our siblings has a higher value already,
+                    // which would be chosen by the parent state
+                    break;
+                }
             }
+            (best_child, best_index)
+        }
+    };
+
+    let value = best_child.value;
+    let cutoff = if maximizing_player {
+        value >= original_beta
+    } else {
+        value <= original_alpha
+    };
+    if cutoff {
+        search.cutoffs.fetch_add(1, Ordering::Relaxed);
+    }
+    if let Some(index) = best_index.filter(|_| cutoff) {
+        search
+            .move_orderer
+            .record_cutoff(&possible_moves[index], ply, depth);
+    }
+
+    if let Some(key) = transposition_key {
+        // Values of an aborted search are meaningless
+        if !search.was_aborted() {
+            let bound = if value <= original_alpha {
+                Bound::Upper
+            } else if value >= original_beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            let solved = search.horizon_nodes.load(Ordering::Relaxed) == horizon_nodes;
+            search.transposition_table.unwrap().store(
+                key,
+                if solved { SOLVED_DEPTH } else { depth },
+                value,
+                bound,
+                best_index.map(|index| possible_moves[index].clone()),
+            );
+        }
+    }
+
+    let mut principal_variation = Vec::with_capacity(best_child.principal_variation.len() + 1);
+    principal_variation.extend(best_index.map(|index| possible_moves[index].clone()));
+    principal_variation.extend(best_child.principal_variation);
+    NodeValue {
+        value,
+        principal_variation,
+    }
+}
+
+/// Value of a state at the depth limit, following only noisy moves. The player to move may
+/// also stop the exchange, so the evaluation of the state bounds the value from their side.
+fn quiescence_value<Rules: GameRules, Eval: Evaluator<Rules = Rules>>(
+    state: &Rules::State,
+    mut alpha: f32,
+    mut beta: f32,
+    maximizing_player: bool,
+    search: &Search<Eval>,
+    remaining_plies: usize, // noisy moves which may still be searched
+) -> NodeValue<Rules::Action> {
+    let stand_pat = NodeValue::leaf(search.eval.value(state, &PlayerIndex::ZERO));
+    if remaining_plies == 0 || state.is_final() || search.should_abort() {
+        return stand_pat;
+    }
+    if maximizing_player {
+        if stand_pat.value >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat.value);
+    } else {
+        if stand_pat.value <= alpha {
+            return stand_pat;
+        }
+        beta = beta.min(stand_pat.value);
+    }
+
+    let mut best = stand_pat;
+    for action in state.get_actions() {
+        if !search.eval.is_noisy(state, &action) {
+            continue;
+        }
+        search.nodes.fetch_add(1, Ordering::Relaxed);
+        let child_value = quiescence_value(
+            &Rules::play(state, &action),
+            alpha,
+            beta,
+            !maximizing_player,
+            search,
+            remaining_plies - 1,
+        );
+        let improves = if maximizing_player {
+            child_value.value > best.value
+        } else {
+            child_value.value < best.value
+        };
+        if improves {
+            let mut principal_variation = vec![action];
+            principal_variation.extend(child_value.principal_variation);
+            best = NodeValue {
+                value: child_value.value,
+                principal_variation,
+            };
+        }
+        if maximizing_player {
+            alpha = alpha.max(best.value);
+        } else {
+            beta = beta.min(best.value);
+        }
+        if alpha >= beta {
+            search.cutoffs.fetch_add(1, Ordering::Relaxed);
+            break;
         }
-        value
     }
+    best
 }
@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+
+use game_ai::{ActionHeuristic, GameRules};
+use rustc_hash::FxHashMap;
+
+/// Heuristics deciding which moves are searched first. Searching good moves first leads to
+/// earlier cutoffs, the result of the search does not change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveOrdering {
+    /// Best move of an earlier search of the same position, requires a transposition table
+    pub transposition_move: bool,
+    /// Moves which recently caused a cutoff at the same ply
+    pub killer_moves: bool,
+    /// Moves which caused cutoffs anywhere in the search, weighted by the depth of the cutoff
+    pub history: bool,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering {
+            transposition_move: true,
+            killer_moves: true,
+            history: true,
+        }
+    }
+}
+
+pub(crate) type SharedActionHeuristic<Rules> =
+    Arc<dyn ActionHeuristic<Rules = Rules> + Send + Sync>;
+
+const KILLER_MOVES_PER_PLY: usize = 2;
+
+/// Move ordering knowledge gathered during the search of one move
+pub(crate) struct MoveOrderer<Rules: GameRules> {
+    ordering: MoveOrdering,
+    action_heuristic: Option<SharedActionHeuristic<Rules>>,
+    killer_moves: Mutex<Vec<[Option<Rules::Action>; KILLER_MOVES_PER_PLY]>>,
+    history: Mutex<FxHashMap<Rules::Action, f32>>,
+}
+
+impl<Rules: GameRules> MoveOrderer<Rules> {
+    pub(crate) fn new(
+        ordering: MoveOrdering,
+        action_heuristic: Option<SharedActionHeuristic<Rules>>,
+    ) -> MoveOrderer<Rules> {
+        MoveOrderer {
+            ordering,
+            action_heuristic,
+            killer_moves: Default::default(),
+            history: Default::default(),
+        }
+    }
+
+    /// Sorts moves so that the most promising ones come first: The transposition table move,
+    /// then killer moves, then all other moves by game heuristic and history score.
+    pub(crate) fn order(
+        &self,
+        state: &Rules::State,
+        moves: &mut [Rules::Action],
+        transposition_move: Option<&Rules::Action>,
+        ply: usize,
+    ) {
+        let transposition_move = transposition_move.filter(|_| self.ordering.transposition_move);
+        let killer_moves = if self.ordering.killer_moves {
+            self.killer_moves.lock().unwrap().get(ply).cloned()
+        } else {
+            None
+        };
+        // The scores are copied, so that other threads can record cutoffs while the moves are
+        // scored
+        let history_scores: Option<Vec<f32>> = if self.ordering.history {
+            let history = self.history.lock().unwrap();
+            (!history.is_empty()).then(|| {
+                moves
+                    .iter()
+                    .map(|action| history.get(action).copied().unwrap_or(0.0))
+                    .collect()
+            })
+        } else {
+            None
+        };
+        if transposition_move.is_none()
+            && killer_moves.is_none()
+            && history_scores.is_none()
+            && self.action_heuristic.is_none()
+        {
+            return;
+        }
+
+        let priority = |index: usize, action: &Rules::Action| {
+            let rank = if Some(action) == transposition_move {
+                0
+            } else if killer_moves
+                .as_ref()
+                .is_some_and(|killers| killers.iter().any(|killer| killer.as_ref() == Some(action)))
+            {
+                1
+            } else {
+                2
+            };
+            let heuristic_score = self
+                .action_heuristic
+                .as_ref()
+                .map_or(0.0, |heuristic| heuristic.score(state, action));
+            let history_score = history_scores.as_ref().map_or(0.0, |scores| scores[index]);
+            (rank, -heuristic_score, -history_score)
+        };
+
+        let mut prioritized: Vec<_> = moves
+            .iter()
+            .enumerate()
+            .map(|(index, action)| (priority(index, action), action.clone()))
+            .collect();
+        prioritized.sort_by(
+            |((rank_1, heuristic_1, history_1), _), ((rank_2, heuristic_2, history_2), _)| {
+                rank_1
+                    .cmp(rank_2)
+                    .then(heuristic_1.total_cmp(heuristic_2))
+                    .then(history_1.total_cmp(history_2))
+            },
+        );
+        for (slot, (_priority, action)) in moves.iter_mut().zip(prioritized) {
+            *slot = action;
+        }
+    }
+
+    /// Remembers a move which caused a cutoff with the given remaining depth
+    pub(crate) fn record_cutoff(&self, action: &Rules::Action, ply: usize, depth: usize) {
+        if self.ordering.killer_moves {
+            let mut killer_moves = self.killer_moves.lock().unwrap();
+            if killer_moves.len() <= ply {
+                killer_moves.resize(ply + 1, Default::default());
+            }
+            let killers = &mut killer_moves[ply];
+            if killers[0].as_ref() != Some(action) {
+                killers.rotate_right(1);
+                killers[0] = Some(action.clone());
+            }
+        }
+        if self.ordering.history {
+            *self
+                .history
+                .lock()
+                .unwrap()
+                .entry(action.clone())
+                .or_default() += (depth * depth) as f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use game_ai::{ActionHeuristic, GameStateTrait};
+    use tic_tac_toe::{TTTAction, TTTRules, TTTState};
+
+    use super::{MoveOrderer, MoveOrdering};
+
+    /// Scores every move as NaN
+    struct Undefined {}
+
+    impl ActionHeuristic for Undefined {
+        type Rules = TTTRules;
+
+        fn score(&self, _state: &TTTState, _action: &TTTAction) -> f32 {
+            f32::NAN
+        }
+    }
+
+    #[test]
+    fn killer_and_transposition_moves_first() {
+        let state = TTTState::default();
+        let orderer = MoveOrderer::<TTTRules>::new(MoveOrdering::default(), None);
+        let all_moves = state.get_actions();
+
+        orderer.record_cutoff(&all_moves[5], 2, 1);
+        let mut moves = all_moves.clone();
+        orderer.order(&state, &mut moves, Some(&all_moves[7]), 2);
+        assert_eq!(moves[0], all_moves[7]);
+        assert_eq!(moves[1], all_moves[5]);
+
+        // Killer moves are only used at the same ply, history everywhere
+        let mut moves = all_moves.clone();
+        orderer.order(&state, &mut moves, None, 3);
+        assert_eq!(moves[0], all_moves[5]);
+        assert_eq!(moves.len(), all_moves.len());
+    }
+
+    #[test]
+    fn nan_scores_do_not_panic() {
+        let state = TTTState::default();
+        let orderer =
+            MoveOrderer::<TTTRules>::new(MoveOrdering::default(), Some(Arc::new(Undefined {})));
+        let all_moves = state.get_actions();
+        orderer.record_cutoff(&all_moves[5], 2, 1);
+        let mut moves = all_moves.clone();
+        orderer.order(&state, &mut moves, Some(&all_moves[7]), 2);
+        assert_eq!(moves[0], all_moves[7]);
+        assert_eq!(moves.len(), all_moves.len());
+    }
+}
@@ -0,0 +1,136 @@
+//! Searches for games with more than two players, which are not zero sum between two sides
+
+use std::sync::atomic::Ordering;
+
+use game_ai::{Evaluator, GameRules, GameStateTrait, PlayerIndex, Rewards};
+
+use crate::{NodeValue, Search};
+
+/// How the players who are not to move at the root are assumed to play
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MultiPlayerSearch {
+    /// max^n: Every player maximizes their own value. Nothing can be pruned.
+    MaxN,
+    /// All other players form a coalition, minimizing the value of the player at the root.
+    /// This reduces the game to two sides, so alpha-beta pruning applies.
+    Paranoid,
+}
+
+/// Values of a node for every player, and the moves the players are expected to play from it
+pub(crate) struct MaxNValue<Action> {
+    pub(crate) values: Rewards,
+    pub(crate) principal_variation: Vec<Action>,
+}
+
+fn evaluate_all<Eval: Evaluator>(
+    eval: &Eval,
+    state: &<Eval::Rules as GameRules>::State,
+) -> Rewards {
+    let mut values = Rewards::default();
+    for player in PlayerIndex::all(Eval::Rules::N_PLAYERS) {
+        values.set_for_player(&player, eval.value(state, &player));
+    }
+    values
+}
+
+/// max^n search, the player to move chooses the first child with the best value for them
+pub(crate) fn max_n_value<Eval: Evaluator>(
+    state: &<Eval::Rules as GameRules>::State,
+    depth: usize,
+    search: &Search<Eval>,
+) -> MaxNValue<<Eval::Rules as GameRules>::Action> {
+    search.nodes.fetch_add(1, Ordering::Relaxed);
+    if depth == 0 && !state.is_final() {
+        search.horizon_nodes.fetch_add(1, Ordering::Relaxed);
+    }
+    if depth == 0 || state.is_final() || search.should_abort() {
+        return MaxNValue {
+            values: evaluate_all(search.eval, state),
+            principal_variation: Vec::new(),
+        };
+    }
+
+    let player = state.next_player();
+    let mut best: Option<(_, MaxNValue<_>)> = None;
+    for action in state.get_actions() {
+        let child_value = max_n_value(&Eval::Rules::play(state, &action), depth - 1, search);
+        let improves = best.as_ref().is_none_or(|(_, best_value)| {
+            child_value.values.for_player(&player) > best_value.values.for_player(&player)
+        });
+        if improves {
+            best = Some((action, child_value));
+        }
+    }
+
+    let (action, child_value) = best.expect("Non-final state without actions");
+    let mut principal_variation = vec![action];
+    principal_variation.extend(child_value.principal_variation);
+    MaxNValue {
+        values: child_value.values,
+        principal_variation,
+    }
+}
+
+/// Paranoid search: Value for `perspective`, who maximizes it, while all other players minimize it
+pub(crate) fn paranoid_value<Eval: Evaluator>(
+    state: &<Eval::Rules as GameRules>::State,
+    depth: usize,
+    mut alpha: f32,
+    mut beta: f32,
+    perspective: &PlayerIndex,
+    search: &Search<Eval>,
+) -> NodeValue<<Eval::Rules as GameRules>::Action> {
+    search.nodes.fetch_add(1, Ordering::Relaxed);
+    if depth == 0 && !state.is_final() {
+        search.horizon_nodes.fetch_add(1, Ordering::Relaxed);
+    }
+    if depth == 0 || state.is_final() || search.should_abort() {
+        return NodeValue::leaf(search.eval.value(state, perspective));
+    }
+
+    let maximizing_player = state.next_player() == *perspective;
+    let ply = search.ply(depth);
+    let mut possible_moves = state.get_actions();
+    search
+        .move_orderer
+        .order(state, &mut possible_moves, None, ply);
+
+    let mut best_child = NodeValue::leaf(if maximizing_player {
+        f32::NEG_INFINITY
+    } else {
+        f32::INFINITY
+    });
+    let mut best_index = None;
+    for (index, action) in possible_moves.iter().enumerate() {
+        let child_state = Eval::Rules::play(state, action);
+        let child_value = paranoid_value(&child_state, depth - 1, alpha, beta, perspective, search);
+        if maximizing_player {
+            if child_value.value > best_child.value {
+                best_child = child_value;
+                best_index = Some(index);
+            }
+            alpha = alpha.max(best_child.value);
+        } else {
+            if child_value.value < best_child.value {
+                best_child = child_value;
+                best_index = Some(index);
+            }
+            beta = beta.min(best_child.value);
+        }
+        if alpha >= beta {
+            search.cutoffs.fetch_add(1, Ordering::Relaxed);
+            search
+                .move_orderer
+                .record_cutoff(&possible_moves[best_index.unwrap()], ply, depth);
+            break;
+        }
+    }
+
+    let mut principal_variation = Vec::with_capacity(best_child.principal_variation.len() + 1);
+    principal_variation.extend(best_index.map(|index| possible_moves[index].clone()));
+    principal_variation.extend(best_child.principal_variation);
+    NodeValue {
+        value: best_child.value,
+        principal_variation,
+    }
+}
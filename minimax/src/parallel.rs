@@ -0,0 +1,129 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Mutex,
+};
+
+use game_ai::GameRules;
+use rayon::prelude::*;
+
+use crate::NodeValue;
+
+/// f32 which can be shared between worker threads, used for alpha-beta bounds
+pub(crate) struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub(crate) fn new(value: f32) -> AtomicF32 {
+        AtomicF32(AtomicU32::new(value.to_bits()))
+    }
+
+    pub(crate) fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Acquire))
+    }
+
+    pub(crate) fn fetch_max(&self, value: f32) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (value > f32::from_bits(current)).then_some(value.to_bits())
+            });
+    }
+
+    pub(crate) fn fetch_min(&self, value: f32) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (value < f32::from_bits(current)).then_some(value.to_bits())
+            });
+    }
+}
+
+/// Maximizing node, searching the first child alone and all younger siblings in parallel,
+/// sharing the improved lower bound between them.
+/// Returns the value of the best child and its index.
+pub(crate) fn maximize_young_brothers_wait<Rules: GameRules>(
+    state: &Rules::State,
+    actions: &[Rules::Action],
+    alpha: f32,
+    beta: f32,
+    child_value: impl Fn(&Rules::State, f32, f32) -> NodeValue<Rules::Action> + Sync,
+) -> (NodeValue<Rules::Action>, Option<usize>)
+where
+    Rules::State: Sync,
+    Rules::Action: Send + Sync,
+{
+    let Some((eldest, younger)) = actions.split_first() else {
+        return (NodeValue::leaf(f32::NEG_INFINITY), None);
+    };
+
+    let eldest_value = child_value(&Rules::play(state, eldest), alpha, beta);
+    if eldest_value.value >= beta {
+        return (eldest_value, Some(0));
+    }
+
+    let alpha = AtomicF32::new(alpha.max(eldest_value.value));
+    let best = Mutex::new((eldest_value, 0));
+    let cutoff = AtomicBool::new(false);
+    younger.par_iter().enumerate().for_each(|(index, action)| {
+        if cutoff.load(Ordering::Relaxed) {
+            // A sibling already proved that the parent will not choose this node
+            return;
+        }
+        let child_state = Rules::play(state, action);
+        let child_value = child_value(&child_state, alpha.load(), beta);
+        if child_value.value >= beta {
+            cutoff.store(true, Ordering::Relaxed);
+        }
+        // The bound is only raised together with the best child. Otherwise a sibling searched
+        // with the raised bound could fail low to the same value and be taken as the best child.
+        let mut best = best.lock().unwrap();
+        if child_value.value > best.0.value {
+            alpha.fetch_max(child_value.value);
+            *best = (child_value, index + 1);
+        }
+    });
+    let (value, index) = best.into_inner().unwrap();
+    (value, Some(index))
+}
+
+/// Minimizing counterpart to [maximize_young_brothers_wait]
+pub(crate) fn minimize_young_brothers_wait<Rules: GameRules>(
+    state: &Rules::State,
+    actions: &[Rules::Action],
+    alpha: f32,
+    beta: f32,
+    child_value: impl Fn(&Rules::State, f32, f32) -> NodeValue<Rules::Action> + Sync,
+) -> (NodeValue<Rules::Action>, Option<usize>)
+where
+    Rules::State: Sync,
+    Rules::Action: Send + Sync,
+{
+    let Some((eldest, younger)) = actions.split_first() else {
+        return (NodeValue::leaf(f32::INFINITY), None);
+    };
+
+    let eldest_value = child_value(&Rules::play(state, eldest), alpha, beta);
+    if eldest_value.value <= alpha {
+        return (eldest_value, Some(0));
+    }
+
+    let beta = AtomicF32::new(beta.min(eldest_value.value));
+    let best = Mutex::new((eldest_value, 0));
+    let cutoff = AtomicBool::new(false);
+    younger.par_iter().enumerate().for_each(|(index, action)| {
+        if cutoff.load(Ordering::Relaxed) {
+            return;
+        }
+        let child_state = Rules::play(state, action);
+        let child_value = child_value(&child_state, alpha, beta.load());
+        if child_value.value <= alpha {
+            cutoff.store(true, Ordering::Relaxed);
+        }
+        let mut best = best.lock().unwrap();
+        if child_value.value < best.0.value {
+            beta.fetch_min(child_value.value);
+            *best = (child_value, index + 1);
+        }
+    });
+    let (value, index) = best.into_inner().unwrap();
+    (value, Some(index))
+}
@@ -0,0 +1,184 @@
+use std::sync::Mutex;
+
+use crate::SOLVED_DEPTH;
+
+/// Relation of a stored value to the true minimax value of the position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Bound {
+    Exact,
+    /// The search failed high, the true value is at least the stored value
+    Lower,
+    /// The search failed low, the true value is at most the stored value
+    Upper,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Entry<Action> {
+    key: u64,
+    pub(crate) depth: usize,
+    pub(crate) value: f32,
+    pub(crate) bound: Bound,
+    pub(crate) best_move: Option<Action>,
+    generation: u32,
+}
+
+/// Decides whether a new entry overwrites the one already stored in its slot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplacementPolicy {
+    /// Always store the newest entry
+    Always,
+    /// Keep entries from deeper searches, unless they are left over from a previous search
+    DepthPreferred,
+}
+
+/// Fixed size hash table of search results, which can be shared between search threads
+pub(crate) struct TranspositionTable<Action> {
+    slots: Vec<Mutex<Option<Entry<Action>>>>,
+    policy: ReplacementPolicy,
+    generation: u32,
+}
+
+impl<Action: Clone> TranspositionTable<Action> {
+    /// Creates a table with as many slots as fit into `memory_limit` bytes
+    pub(crate) fn new(
+        memory_limit: usize,
+        policy: ReplacementPolicy,
+    ) -> TranspositionTable<Action> {
+        let slot_count =
+            (memory_limit / std::mem::size_of::<Mutex<Option<Entry<Action>>>>()).max(1);
+        TranspositionTable {
+            slots: (0..slot_count).map(|_| Mutex::new(None)).collect(),
+            policy,
+            generation: 0,
+        }
+    }
+
+    /// Marks all stored entries as belonging to a previous search
+    pub(crate) fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    fn slot(&self, key: u64) -> &Mutex<Option<Entry<Action>>> {
+        &self.slots[(key % self.slots.len() as u64) as usize]
+    }
+
+    pub(crate) fn probe(&self, key: u64) -> Option<Entry<Action>> {
+        self.slot(key)
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|entry| entry.key == key)
+            .cloned()
+    }
+
+    pub(crate) fn store(
+        &self,
+        key: u64,
+        depth: usize,
+        value: f32,
+        bound: Bound,
+        best_move: Option<Action>,
+    ) {
+        let mut slot = self.slot(key).lock().unwrap();
+        let replace = match (self.policy, slot.as_ref()) {
+            (ReplacementPolicy::Always, _) | (_, None) => true,
+            // A solved position keeps its value, other results of the same position replace it
+            // unless they are shallower bounds
+            (ReplacementPolicy::DepthPreferred, Some(stored)) if stored.key == key => {
+                (stored.depth != SOLVED_DEPTH || depth == SOLVED_DEPTH)
+                    && (depth >= stored.depth || bound == Bound::Exact)
+            }
+            (ReplacementPolicy::DepthPreferred, Some(stored)) => {
+                stored.generation != self.generation || depth >= stored.depth
+            }
+        };
+        if replace {
+            *slot = Some(Entry {
+                key,
+                depth,
+                value,
+                bound,
+                best_move,
+                generation: self.generation,
+            });
+        }
+    }
+}
+
+impl<Action: Clone> Clone for TranspositionTable<Action> {
+    fn clone(&self) -> Self {
+        TranspositionTable {
+            slots: self
+                .slots
+                .iter()
+                .map(|slot| Mutex::new(slot.lock().unwrap().clone()))
+                .collect(),
+            policy: self.policy,
+            generation: self.generation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, ReplacementPolicy, TranspositionTable};
+    use crate::SOLVED_DEPTH;
+
+    #[test]
+    fn store_and_probe() {
+        let table = TranspositionTable::<u8>::new(1 << 16, ReplacementPolicy::Always);
+        assert!(table.probe(42).is_none());
+
+        table.store(42, 3, 1.5, Bound::Lower, Some(7));
+        let entry = table.probe(42).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.value, 1.5);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.best_move, Some(7));
+
+        // Same slot, but different key
+        assert!(table.probe(42 + table.slots.len() as u64).is_none());
+    }
+
+    #[test]
+    fn depth_preferred_replacement() {
+        let mut table = TranspositionTable::<u8>::new(0, ReplacementPolicy::DepthPreferred);
+        assert_eq!(table.slots.len(), 1);
+
+        table.store(1, 5, 0.0, Bound::Exact, None);
+        table.store(2, 4, 0.0, Bound::Exact, None);
+        assert!(
+            table.probe(1).is_some(),
+            "Shallower entry must not replace deeper one"
+        );
+
+        table.store(1, 2, 0.0, Bound::Lower, None);
+        assert_eq!(
+            table.probe(1).unwrap().depth,
+            5,
+            "Shallower bound of the same position must not replace deeper entry"
+        );
+
+        table.store(1, 2, 0.0, Bound::Exact, None);
+        assert_eq!(
+            table.probe(1).unwrap().depth,
+            2,
+            "Exact value of the same position is always stored"
+        );
+
+        table.store(1, SOLVED_DEPTH, 1.0, Bound::Exact, None);
+        table.store(1, 8, 0.0, Bound::Exact, None);
+        assert_eq!(
+            table.probe(1).unwrap().depth,
+            SOLVED_DEPTH,
+            "Solved position must not be replaced by an unsolved one"
+        );
+
+        table.new_search();
+        table.store(2, 1, 0.0, Bound::Exact, None);
+        assert!(
+            table.probe(2).is_some(),
+            "Entries of old searches are replaced"
+        );
+    }
+}
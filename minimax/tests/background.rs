@@ -0,0 +1,49 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use game_ai::{AsyncGameAi, BackgroundAi, GameStateTrait, StopCondition};
+use hexxagon_lib::{ai::HexxagonEvaluator, game::GameState};
+use minimax::MiniMax;
+
+#[test]
+fn move_now_plays_last_completed_depth() {
+    let mut ai = BackgroundAi::new(MiniMax::new_iterative_deepening(
+        StopCondition::Time(Duration::from_secs(60)),
+        HexxagonEvaluator::default(),
+    ));
+    let state = GameState::default();
+    let start = Instant::now();
+    ai.start_search(&state);
+
+    // Depth 0 always completes
+    while ai.progress().best_move.is_none() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    let progress = ai.progress();
+    assert!(progress.nodes > 0);
+    assert!(ai.is_searching());
+
+    let action = ai.move_now().unwrap();
+    assert!(state.get_actions().contains(&action));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn fixed_depth_search_can_be_stopped() {
+    // Far too deep to complete
+    let mut ai = BackgroundAi::new(MiniMax::new(20, HexxagonEvaluator::default()));
+    let state = GameState::default();
+    let start = Instant::now();
+    ai.start_search(&state);
+
+    // Depth 0 is searched first, to have a move to play
+    while ai.progress().best_move.is_none() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(ai.progress().depth, 0);
+    let action = ai.move_now().unwrap();
+    assert!(state.get_actions().contains(&action));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
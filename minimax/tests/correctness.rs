@@ -0,0 +1,157 @@
+use game_ai::{Evaluator, GameRules, GameStateTrait, PlayerIndex};
+use hexxagon_lib::{
+    ai::{move_generation::sample_valid_move, CaptureHeuristic, HexxagonEvaluator},
+    game::{rules::HexxagonRules, GameState},
+};
+use minimax::{MiniMax, Parallelism, ReplacementPolicy};
+use rustc_hash::FxHashMap;
+use tic_tac_toe::{TTTEvaluator, TTTRules, TTTState};
+
+/// Plain minimax without any pruning, value for player zero. Results are cached by state and
+/// remaining depth.
+fn exhaustive_minimax<Eval: Evaluator>(
+    eval: &Eval,
+    state: &<Eval::Rules as GameRules>::State,
+    depth: usize,
+    cache: &mut FxHashMap<(u64, usize), f32>,
+) -> f32 {
+    if depth == 0 || state.is_final() {
+        return eval.value(state, &PlayerIndex::ZERO);
+    }
+    let cache_key = state.hash_key().map(|key| (key, depth));
+    if let Some(value) = cache_key.and_then(|key| cache.get(&key)) {
+        return *value;
+    }
+
+    let values = state.get_actions().into_iter().map(|action| {
+        exhaustive_minimax(eval, &Eval::Rules::play(state, &action), depth - 1, cache)
+    });
+    let value = if state.next_player().is_maximizing() {
+        values.fold(f32::NEG_INFINITY, f32::max)
+    } else {
+        values.fold(f32::INFINITY, f32::min)
+    };
+    if let Some(key) = cache_key {
+        cache.insert(key, value);
+    }
+    value
+}
+
+/// Searches that have to agree with plain minimax
+fn configurations<Eval: Evaluator + Clone>(depth: usize, eval: Eval) -> Vec<MiniMax<Eval>> {
+    vec![
+        MiniMax::new(depth, eval.clone()),
+        MiniMax::new(depth, eval.clone())
+            .with_transposition_table(1 << 20, ReplacementPolicy::DepthPreferred),
+        MiniMax::new(depth, eval).with_parallelism(Parallelism::YoungBrothersWait { plies: 2 }),
+    ]
+}
+
+/// Checks the value and move of `ai` against plain minimax searching `depth` plies after the root
+fn check_search<Eval>(
+    ai: &mut MiniMax<Eval>,
+    state: &<Eval::Rules as GameRules>::State,
+    depth: usize,
+    eval: &Eval,
+    cache: &mut FxHashMap<(u64, usize), f32>,
+) where
+    Eval: Evaluator + Clone + Sync,
+    <Eval::Rules as GameRules>::State: Sync,
+    <Eval::Rules as GameRules>::Action: Send + Sync,
+{
+    let expected = exhaustive_minimax(eval, state, depth + 1, cache);
+    let report = ai.search(state);
+    let reported = if state.next_player().is_maximizing() {
+        report.value
+    } else {
+        -report.value
+    };
+    assert_eq!(reported, expected, "Value of {:?}", state);
+
+    let best_move_value = exhaustive_minimax(
+        eval,
+        &Eval::Rules::play(state, &report.best_move),
+        depth,
+        cache,
+    );
+    assert_eq!(
+        best_move_value, expected,
+        "Move {:?} in {:?}",
+        report.best_move, state
+    );
+}
+
+fn reachable_tic_tac_toe_states() -> Vec<TTTState> {
+    let mut states = FxHashMap::default();
+    let mut unexplored = vec![TTTState::default()];
+    while let Some(state) = unexplored.pop() {
+        if states
+            .insert(state.hash_key().unwrap(), state.clone())
+            .is_some()
+            || state.is_final()
+        {
+            continue;
+        }
+        for action in state.get_actions() {
+            unexplored.push(TTTRules::play(&state, &action));
+        }
+    }
+    states.into_values().collect()
+}
+
+#[test]
+fn tic_tac_toe_matches_exhaustive_minimax() {
+    let states = reachable_tic_tac_toe_states();
+    assert_eq!(states.len(), 5478, "Number of legal tic tac toe positions");
+
+    let mut cache = FxHashMap::default();
+    // Solves the game from every position
+    for mut ai in configurations(8, TTTEvaluator {}) {
+        for state in states.iter().filter(|state| !state.is_final()) {
+            check_search(&mut ai, state, 8, &TTTEvaluator {}, &mut cache);
+        }
+    }
+    assert_eq!(
+        exhaustive_minimax(&TTTEvaluator {}, &TTTState::default(), 9, &mut cache),
+        0.0,
+        "Tic tac toe is a draw"
+    );
+}
+
+fn random_position(moves: usize) -> GameState {
+    let mut state = GameState::default();
+    for _ in 0..moves {
+        if state.is_final() {
+            break;
+        }
+        state = HexxagonRules::play(&state, &sample_valid_move(&state));
+    }
+    state
+}
+
+#[test]
+fn hexxagon_matches_exhaustive_minimax() {
+    let mut cache = FxHashMap::default();
+    for moves in [0, 1, 2, 5, 8, 13] {
+        let state = random_position(moves);
+        if state.is_final() {
+            continue;
+        }
+        for depth in 0..=1 {
+            let mut ais = configurations(depth, HexxagonEvaluator::default());
+            ais.push(
+                MiniMax::new(depth, HexxagonEvaluator::default())
+                    .with_action_heuristic(CaptureHeuristic {}),
+            );
+            for mut ai in ais {
+                check_search(
+                    &mut ai,
+                    &state,
+                    depth,
+                    &HexxagonEvaluator::default(),
+                    &mut cache,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+use game_ai::{GameAi, GameStateTrait, StopCondition};
+use hexxagon_lib::{ai::HexxagonEvaluator, game::GameState};
+use minimax::{MiniMax, ReplacementPolicy};
+use tic_tac_toe::{TTTEvaluator, TTTState};
+
+#[test]
+fn iterations_match_fixed_depth() {
+    let state = GameState::default();
+
+    let fixed_depth_move =
+        MiniMax::new(2, HexxagonEvaluator::default()).determine_next_move(&state);
+    let mut ai = MiniMax::new_iterative_deepening(
+        StopCondition::Iterations(3),
+        HexxagonEvaluator::default(),
+    );
+    assert_eq!(ai.determine_next_move(&state), fixed_depth_move);
+}
+
+#[test]
+fn time_budget_is_respected() {
+    let state = GameState::default();
+    let budget = Duration::from_millis(200);
+
+    let mut ai =
+        MiniMax::new_iterative_deepening(StopCondition::Time(budget), HexxagonEvaluator::default());
+    let start = Instant::now();
+    let action = ai.determine_next_move(&state);
+    assert!(start.elapsed() < 2 * budget);
+    assert!(state.get_actions().contains(&action));
+}
+
+#[test]
+fn solved_positions_stop_deepening() {
+    let budget = Duration::from_secs(60);
+    for mut ai in [
+        MiniMax::new_iterative_deepening(StopCondition::Time(budget), TTTEvaluator {}),
+        MiniMax::new_iterative_deepening(StopCondition::Time(budget), TTTEvaluator {})
+            .with_transposition_table(1 << 20, ReplacementPolicy::DepthPreferred),
+    ] {
+        let report = ai.search(&TTTState::default());
+        // Nine moves fill the board
+        assert!(report.depth <= 9);
+        assert!(report.elapsed < budget / 2);
+    }
+}
@@ -0,0 +1,24 @@
+use game_ai::GameAi;
+use hexxagon_lib::{
+    ai::{CaptureHeuristic, HexxagonEvaluator},
+    game::GameState,
+};
+use minimax::{MiniMax, MoveOrdering};
+
+#[test]
+fn ordering_does_not_change_move() {
+    let state = GameState::default();
+
+    let no_ordering = MoveOrdering {
+        transposition_move: false,
+        killer_moves: false,
+        history: false,
+    };
+    let unordered_move = MiniMax::new(2, HexxagonEvaluator::default())
+        .with_move_ordering(no_ordering)
+        .determine_next_move(&state);
+
+    let mut ai =
+        MiniMax::new(2, HexxagonEvaluator::default()).with_action_heuristic(CaptureHeuristic {});
+    assert_eq!(ai.determine_next_move(&state), unordered_move);
+}
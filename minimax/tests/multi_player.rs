@@ -0,0 +1,170 @@
+use game_ai::{Evaluator, GameAi, GameRules, GameStateTrait, PlayerIndex, Rewards};
+use minimax::{MiniMax, MultiPlayerSearch};
+
+const N_PLAYERS: usize = 3;
+
+/// Players take turns taking a number from either end of a row, and score its value
+#[derive(Clone, Debug)]
+struct RowState {
+    row: Vec<u8>,
+    scores: [f32; N_PLAYERS],
+    next_player: PlayerIndex,
+}
+
+impl Default for RowState {
+    fn default() -> Self {
+        RowState {
+            row: vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5],
+            scores: [0.0; N_PLAYERS],
+            next_player: PlayerIndex::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum Take {
+    Left,
+    Right,
+}
+
+impl GameStateTrait<Take> for RowState {
+    fn is_final(&self) -> bool {
+        self.row.is_empty()
+    }
+
+    fn get_actions(&self) -> Vec<Take> {
+        if self.row.len() == 1 {
+            vec![Take::Left]
+        } else {
+            vec![Take::Left, Take::Right]
+        }
+    }
+
+    fn reward(&self) -> Rewards {
+        Rewards::new(&self.scores)
+    }
+
+    fn next_player(&self) -> PlayerIndex {
+        self.next_player
+    }
+}
+
+struct RowRules {}
+
+impl GameRules for RowRules {
+    type Action = Take;
+    type State = RowState;
+    const N_PLAYERS: usize = N_PLAYERS;
+
+    fn play(initial_state: &RowState, action: &Take) -> RowState {
+        let mut state = initial_state.clone();
+        let taken = match action {
+            Take::Left => state.row.remove(0),
+            Take::Right => state.row.pop().unwrap(),
+        };
+        state.scores[state.next_player.index()] += taken as f32;
+        state.next_player = state.next_player.next(N_PLAYERS);
+        state
+    }
+}
+
+#[derive(Clone)]
+struct ScoreEvaluator {}
+
+impl Evaluator for ScoreEvaluator {
+    type Rules = RowRules;
+
+    fn value(&self, state: &RowState, player: &PlayerIndex) -> f32 {
+        state.scores[player.index()]
+    }
+}
+
+/// Exhaustive max^n, every player chooses the first move which is best for them
+fn max_n(state: &RowState, depth: usize) -> [f32; N_PLAYERS] {
+    if depth == 0 || state.is_final() {
+        return state.scores;
+    }
+    let player = state.next_player().index();
+    let mut best: Option<[f32; N_PLAYERS]> = None;
+    for action in state.get_actions() {
+        let values = max_n(&RowRules::play(state, &action), depth - 1);
+        if best.is_none_or(|best| values[player] > best[player]) {
+            best = Some(values);
+        }
+    }
+    best.unwrap()
+}
+
+/// Exhaustive paranoid search for the player at index `perspective`
+fn paranoid(state: &RowState, depth: usize, perspective: usize) -> f32 {
+    if depth == 0 || state.is_final() {
+        return state.scores[perspective];
+    }
+    let values = state
+        .get_actions()
+        .into_iter()
+        .map(|action| paranoid(&RowRules::play(state, &action), depth - 1, perspective));
+    if state.next_player().index() == perspective {
+        values.fold(f32::NEG_INFINITY, f32::max)
+    } else {
+        values.fold(f32::INFINITY, f32::min)
+    }
+}
+
+/// The initial state, and states reached by playing some first moves
+fn openings() -> Vec<RowState> {
+    let mut states = vec![RowState::default()];
+    for _ in 0..N_PLAYERS {
+        let last = states.last().unwrap().clone();
+        states.extend(
+            last.get_actions()
+                .iter()
+                .map(|action| RowRules::play(&last, action)),
+        );
+    }
+    states
+}
+
+#[test]
+fn max_n_matches_exhaustive_search() {
+    for state in openings() {
+        for depth in 0..5 {
+            let player = state.next_player();
+            let expected = state
+                .get_actions()
+                .into_iter()
+                .map(|action| max_n(&RowRules::play(&state, &action), depth)[player.index()])
+                .fold(f32::NEG_INFINITY, f32::max);
+
+            let mut ai = MiniMax::new(depth, ScoreEvaluator {})
+                .with_multi_player_search(MultiPlayerSearch::MaxN);
+            let report = ai.search(&state);
+            assert_eq!(report.value, expected, "{:?} at depth {}", state, depth);
+            assert_eq!(report.principal_variation[0], report.best_move);
+        }
+    }
+}
+
+#[test]
+fn paranoid_matches_exhaustive_search() {
+    for state in openings() {
+        for depth in 0..6 {
+            let player = state.next_player().index();
+            let expected = state
+                .get_actions()
+                .into_iter()
+                .map(|action| paranoid(&RowRules::play(&state, &action), depth, player))
+                .fold(f32::NEG_INFINITY, f32::max);
+
+            let mut ai = MiniMax::new(depth, ScoreEvaluator {})
+                .with_multi_player_search(MultiPlayerSearch::Paranoid);
+            let report = ai.search(&state);
+            assert_eq!(report.value, expected, "{:?} at depth {}", state, depth);
+            assert_eq!(
+                report.best_move,
+                ai.determine_next_move(&state),
+                "Search is deterministic"
+            );
+        }
+    }
+}
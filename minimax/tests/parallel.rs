@@ -0,0 +1,90 @@
+use game_ai::{Evaluator, GameAi, GameRules, GameStateTrait, PlayerIndex};
+use hexxagon_lib::{
+    ai::{move_generation::sample_valid_move, HexxagonEvaluator},
+    game::{rules::HexxagonRules, GameState},
+};
+use minimax::{MiniMax, Parallelism};
+
+fn random_position(moves: usize) -> GameState {
+    let mut state = GameState::default();
+    for _ in 0..moves {
+        if state.is_final() {
+            break;
+        }
+        state = HexxagonRules::play(&state, &sample_valid_move(&state));
+    }
+    state
+}
+
+/// Value of the state for the first player, by a sequential search of `plies`
+fn sequential_value(state: &GameState, plies: usize) -> f32 {
+    if plies == 0 || state.is_final() {
+        return HexxagonEvaluator::default().value(state, &PlayerIndex::ZERO);
+    }
+    // The depth does not count the move at the root
+    let report = MiniMax::new(plies - 1, HexxagonEvaluator::default()).search(state);
+    if GameStateTrait::next_player(state).is_maximizing() {
+        report.value
+    } else {
+        -report.value
+    }
+}
+
+#[test]
+fn parallel_search_matches_sequential() {
+    for moves in [0, 1, 6, 11] {
+        let state = random_position(moves);
+        if state.is_final() {
+            continue;
+        }
+
+        let sequential_move =
+            MiniMax::new(2, HexxagonEvaluator::default()).determine_next_move(&state);
+        for parallelism in [
+            Parallelism::RootSplit,
+            Parallelism::YoungBrothersWait { plies: 1 },
+        ] {
+            let mut ai =
+                MiniMax::new(2, HexxagonEvaluator::default()).with_parallelism(parallelism);
+            assert_eq!(ai.determine_next_move(&state), sequential_move);
+        }
+    }
+}
+
+#[test]
+fn young_brothers_wait_matches_sequential_variation() {
+    const DEPTH: usize = 3;
+    for moves in [0, 6, 11, 20] {
+        let state = random_position(moves);
+        if state.is_final() {
+            continue;
+        }
+
+        let sequential = MiniMax::new(DEPTH, HexxagonEvaluator::default()).search(&state);
+        let report = MiniMax::new(DEPTH, HexxagonEvaluator::default())
+            .with_parallelism(Parallelism::YoungBrothersWait { plies: 2 })
+            .search(&state);
+        assert_eq!(report.best_move, sequential.best_move);
+        assert_eq!(report.value, sequential.value);
+        assert_eq!(
+            report.principal_variation.len(),
+            sequential.principal_variation.len()
+        );
+
+        // Tied moves may differ from the sequential variation, but every move has to keep the
+        // value, as sequential searches of the following positions confirm
+        let plies = report.principal_variation.len();
+        let expected = sequential_value(&state, plies);
+        let mut current = state.clone();
+        for (ply, action) in report.principal_variation.iter().enumerate() {
+            current = HexxagonRules::play(&current, action);
+            assert_eq!(
+                sequential_value(&current, plies - ply - 1),
+                expected,
+                "Move {} of {:?}",
+                ply,
+                report.principal_variation
+            );
+        }
+    }
+}
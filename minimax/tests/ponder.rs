@@ -0,0 +1,40 @@
+use std::{thread, time::Duration};
+
+use game_ai::{GameAi, GameRules, GameStateTrait};
+use minimax::{MiniMax, ReplacementPolicy};
+use tic_tac_toe::{TTTEvaluator, TTTRules, TTTState};
+
+const PONDER_TIME: Duration = Duration::from_millis(200);
+
+fn ai() -> MiniMax<TTTEvaluator> {
+    MiniMax::new(4, TTTEvaluator {})
+        .with_transposition_table(1 << 20, ReplacementPolicy::DepthPreferred)
+}
+
+#[test]
+fn pondering_fills_transposition_table() {
+    let state = TTTState::default();
+    let state = TTTRules::play(&state, &state.get_actions()[0]);
+    let reply = TTTRules::play(&state, &state.get_actions()[0]);
+    let nodes_without_pondering = ai().search(&reply).nodes;
+
+    let mut ai = ai();
+    ai.start_pondering(&state);
+    thread::sleep(PONDER_TIME);
+    let report = ai.search(&reply);
+    assert!(reply.get_actions().contains(&report.best_move));
+    assert!(report.nodes < nodes_without_pondering);
+}
+
+#[test]
+fn pondering_requires_transposition_table() {
+    let state = TTTState::default();
+    let reply = TTTRules::play(&state, &state.get_actions()[0]);
+    let nodes_without_pondering = MiniMax::new(4, TTTEvaluator {}).search(&reply).nodes;
+
+    let mut ai = MiniMax::new(4, TTTEvaluator {});
+    ai.start_pondering(&state);
+    thread::sleep(PONDER_TIME);
+    ai.stop_pondering();
+    assert_eq!(ai.search(&reply).nodes, nodes_without_pondering);
+}
@@ -0,0 +1,105 @@
+use game_ai::{Evaluator, GameAi, GameRules, GameStateTrait, PlayerIndex};
+use hexxagon_lib::{
+    ai::{move_generation::sample_valid_move, HexxagonEvaluator},
+    game::{rules::HexxagonRules, GameState},
+};
+use minimax::MiniMax;
+use tic_tac_toe::{TTTEvaluator, TTTState};
+
+fn random_position(moves: usize) -> GameState {
+    let mut state = GameState::default();
+    for _ in 0..moves {
+        if state.is_final() {
+            break;
+        }
+        state = HexxagonRules::play(&state, &sample_valid_move(&state));
+    }
+    state
+}
+
+/// Evaluation of the state, or of the best exchange of up to `plies` noisy moves
+fn exhaustive_quiescence(state: &GameState, plies: usize) -> f32 {
+    let eval = HexxagonEvaluator::default();
+    let stand_pat = eval.value(state, &PlayerIndex::ZERO);
+    if plies == 0 || state.is_final() {
+        return stand_pat;
+    }
+    let values = state
+        .get_actions()
+        .into_iter()
+        .filter(|action| eval.is_noisy(state, action))
+        .map(|action| exhaustive_quiescence(&HexxagonRules::play(state, &action), plies - 1));
+    if GameStateTrait::next_player(state).is_maximizing() {
+        values.fold(stand_pat, f32::max)
+    } else {
+        values.fold(stand_pat, f32::min)
+    }
+}
+
+fn exhaustive_minimax(state: &GameState, depth: usize, quiescence_plies: usize) -> f32 {
+    if state.is_final() {
+        return HexxagonEvaluator::default().value(state, &PlayerIndex::ZERO);
+    }
+    if depth == 0 {
+        return exhaustive_quiescence(state, quiescence_plies);
+    }
+    let values = state.get_actions().into_iter().map(|action| {
+        exhaustive_minimax(
+            &HexxagonRules::play(state, &action),
+            depth - 1,
+            quiescence_plies,
+        )
+    });
+    if GameStateTrait::next_player(state).is_maximizing() {
+        values.fold(f32::NEG_INFINITY, f32::max)
+    } else {
+        values.fold(f32::INFINITY, f32::min)
+    }
+}
+
+#[test]
+fn quiescence_matches_exhaustive_search() {
+    // Noisy moves become common once the field fills up
+    for moves in [10, 30, 40, 60] {
+        let state = random_position(moves);
+        if state.is_final() {
+            continue;
+        }
+        let expected = exhaustive_minimax(&state, 1, 3);
+        let report = MiniMax::new(0, HexxagonEvaluator::default())
+            .with_quiescence(3)
+            .search(&state);
+        let reported = if GameStateTrait::next_player(&state).is_maximizing() {
+            report.value
+        } else {
+            -report.value
+        };
+        assert_eq!(reported, expected, "Value of {:?}", state);
+    }
+}
+
+#[test]
+fn noisy_captures_are_configurable() {
+    let state = random_position(40);
+    let plain = MiniMax::new(1, HexxagonEvaluator::default()).search(&state);
+    let without_noisy_moves = HexxagonEvaluator {
+        noisy_captures: usize::MAX,
+    };
+    let quiescence = MiniMax::new(1, without_noisy_moves)
+        .with_quiescence(3)
+        .search(&state);
+    assert_eq!(quiescence.nodes, plain.nodes);
+    assert_eq!(quiescence.value, plain.value);
+}
+
+#[test]
+fn quiet_games_are_not_extended() {
+    // Tic tac toe has no noisy moves
+    let state = TTTState::default();
+    let plain = MiniMax::new(2, TTTEvaluator {}).search(&state);
+    let mut ai = MiniMax::new(2, TTTEvaluator {}).with_quiescence(4);
+    let quiescence = ai.search(&state);
+    assert_eq!(quiescence.nodes, plain.nodes);
+    assert_eq!(quiescence.best_move, plain.best_move);
+    assert_eq!(ai.name(), "MiniMax (depth 2, quiescence 4)");
+}
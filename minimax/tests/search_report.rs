@@ -0,0 +1,70 @@
+use game_ai::{
+    Evaluator, GameAi, GameRules, GameStateTrait, PlayerIndex, SearchControl, StopCondition,
+};
+use hexxagon_lib::{ai::HexxagonEvaluator, game::rules::HexxagonRules};
+use minimax::MiniMax;
+use tic_tac_toe::{TTTEvaluator, TTTRules, TTTState};
+
+#[test]
+fn principal_variation_is_playable() {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+    let mut ai = MiniMax::new(2, HexxagonEvaluator::default());
+    let report = ai.search(&initial_state);
+
+    assert_eq!(report.principal_variation[0], report.best_move);
+    assert_eq!(report.best_move, ai.determine_next_move(&initial_state));
+    assert_eq!(report.depth, 2);
+    assert!(report.nodes > 0);
+
+    let mut state = initial_state;
+    for action in &report.principal_variation {
+        assert!(state.get_actions().contains(action));
+        state = HexxagonRules::play(&state, action);
+    }
+}
+
+#[test]
+fn progress_reports_value_and_principal_variation() {
+    // The second player is to move, whose value is negated
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+    let state = HexxagonRules::play(&initial_state, &initial_state.get_actions()[0]);
+    let mut ai = MiniMax::new(2, HexxagonEvaluator::default());
+    let report = ai.search(&state);
+
+    let control = SearchControl::new();
+    ai.determine_next_move_with_control(&state, &control);
+    let progress = control.progress();
+    assert_eq!(progress.value, Some(report.value));
+    assert_eq!(progress.principal_variation, report.principal_variation);
+}
+
+#[test]
+fn principal_variation_of_solved_game() {
+    // Searching to the end of the game, the principal variation ends in a final state with the
+    // reported value
+    let mut ai = MiniMax::new(9, TTTEvaluator {});
+    let report = ai.search(&TTTState::default());
+
+    let mut state = TTTState::default();
+    for action in &report.principal_variation {
+        state = TTTRules::play(&state, action);
+    }
+    assert!(state.is_final());
+    assert_eq!(
+        report.value,
+        TTTEvaluator {}.value(&state, &PlayerIndex::ZERO)
+    );
+    assert!(report.cutoffs > 0);
+}
+
+#[test]
+fn iterative_deepening_reports_completed_depth() {
+    let initial_state = <HexxagonRules as GameRules>::State::default();
+    let mut ai = MiniMax::new_iterative_deepening(
+        StopCondition::Iterations(3),
+        HexxagonEvaluator::default(),
+    );
+    let report = ai.search(&initial_state);
+    assert_eq!(report.depth, 2);
+    assert_eq!(report.principal_variation[0], report.best_move);
+}
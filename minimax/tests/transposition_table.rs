@@ -0,0 +1,28 @@
+use game_ai::{GameAi, GameStateTrait, StopCondition};
+use hexxagon_lib::{ai::HexxagonEvaluator, game::GameState};
+use minimax::{MiniMax, ReplacementPolicy};
+use tic_tac_toe::{TTTEvaluator, TTTState};
+
+#[test]
+fn same_move_as_without_table() {
+    let state = TTTState::default();
+
+    let move_without_table = MiniMax::new(9, TTTEvaluator {}).determine_next_move(&state);
+    for policy in [ReplacementPolicy::Always, ReplacementPolicy::DepthPreferred] {
+        let mut ai = MiniMax::new(9, TTTEvaluator {}).with_transposition_table(1 << 20, policy);
+        assert_eq!(ai.determine_next_move(&state), move_without_table);
+    }
+}
+
+#[test]
+fn reuse_between_iterations() {
+    let state = GameState::default();
+
+    let mut ai = MiniMax::new_iterative_deepening(
+        StopCondition::Iterations(3),
+        HexxagonEvaluator::default(),
+    )
+    .with_transposition_table(1 << 16, ReplacementPolicy::DepthPreferred);
+    let action = ai.determine_next_move(&state);
+    assert!(state.get_actions().contains(&action));
+}
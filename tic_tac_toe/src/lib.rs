@@ -1,4 +1,7 @@
-use game_ai::{GameRules, GameStateTrait, PlayerIndex, Rewards};
+use game_ai::{Evaluator, GameRules, GameStateTrait, ModelFeatures, PlayerIndex, Rewards};
+
+#[cfg(any(test, feature = "test-support"))]
+mod test_support;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct TTTAction {
@@ -14,14 +17,8 @@ pub enum TTTPlayer {
 impl TTTPlayer {
     fn winner_to_reward(&self) -> Rewards {
         match self {
-            TTTPlayer::X => Rewards {
-                player_0: 1.0,
-                player_1: 0.0,
-            },
-            TTTPlayer::O => Rewards {
-                player_0: 0.0,
-                player_1: 1.0,
-            },
+            TTTPlayer::X => Rewards::new(&[1.0, 0.0]),
+            TTTPlayer::O => Rewards::new(&[0.0, 1.0]),
         }
     }
 }
@@ -37,8 +34,8 @@ impl std::fmt::Display for TTTPlayer {
 impl From<TTTPlayer> for PlayerIndex {
     fn from(value: TTTPlayer) -> Self {
         match value {
-            TTTPlayer::X => PlayerIndex::Zero,
-            TTTPlayer::O => PlayerIndex::One,
+            TTTPlayer::X => PlayerIndex::ZERO,
+            TTTPlayer::O => PlayerIndex::ONE,
         }
     }
 }
@@ -171,15 +168,31 @@ impl GameStateTrait<TTTAction> for TTTState {
 
         self.winner()
             .map(|w| w.winner_to_reward())
-            .unwrap_or(Rewards {
-                player_0: 0.5,
-                player_1: 0.5,
-            })
+            .unwrap_or(Rewards::new(&[0.5, 0.5]))
     }
 
     fn next_player(&self) -> PlayerIndex {
         PlayerIndex::from(self.next_player.clone())
     }
+
+    fn hash_key(&self) -> Option<u64> {
+        // Base 3 representation of the board, which is unique for every state
+        let mut key = match self.next_player {
+            TTTPlayer::X => 0,
+            TTTPlayer::O => 1,
+        };
+        for row in self.board.iter() {
+            for cell in row.iter() {
+                key = 3 * key
+                    + match cell {
+                        GridCell::Empty => 0,
+                        GridCell::Occupied(TTTPlayer::X) => 1,
+                        GridCell::Occupied(TTTPlayer::O) => 2,
+                    };
+            }
+        }
+        Some(key)
+    }
 }
 
 impl Default for TTTState {
@@ -198,7 +211,7 @@ impl GameRules for TTTRules {
 
     type State = TTTState;
 
-    const N_PLAYERS: u32 = 2;
+    const N_PLAYERS: usize = 2;
 
     fn play(initial_state: &Self::State, action: &Self::Action) -> Self::State {
         assert!(action.row < 3);
@@ -215,3 +228,72 @@ impl GameRules for TTTRules {
         new_state
     }
 }
+
+/// Values final states by their outcome, all other states as a draw
+#[derive(Clone)]
+pub struct TTTEvaluator {}
+
+impl Evaluator for TTTEvaluator {
+    type Rules = TTTRules;
+
+    fn value(&self, state: &TTTState, player: &PlayerIndex) -> f32 {
+        if state.is_final() {
+            let reward = state.reward();
+            reward.for_player(player) - reward.for_player(&player.opponent())
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Features for a [game_ai::LinearModel]: The cells of both players, and for actions the cell
+/// and whether it completes a line of either player
+#[derive(Clone)]
+pub struct TTTFeatures {}
+
+impl TTTFeatures {
+    /// Whether `player` completes a line by taking the cell of `action`
+    fn completes_line(state: &TTTState, action: &TTTAction, player: &TTTPlayer) -> bool {
+        let mut board = state.clone();
+        board.board[action.row][action.col] = GridCell::Occupied(player.clone());
+        board.winner().as_ref() == Some(player)
+    }
+}
+
+impl ModelFeatures for TTTFeatures {
+    type Rules = TTTRules;
+
+    const STATE_FEATURES: usize = 19;
+    const ACTION_FEATURES: usize = 11;
+
+    fn state_features(&self, state: &TTTState) -> Vec<f32> {
+        let mut features = vec![0.0; Self::STATE_FEATURES];
+        features[0] = 1.0;
+        for (index, cell) in state.board.iter().flatten().enumerate() {
+            match cell {
+                GridCell::Occupied(player) if *player == state.next_player => {
+                    features[1 + index] = 1.0
+                }
+                GridCell::Occupied(_) => features[10 + index] = 1.0,
+                GridCell::Empty => {}
+            }
+        }
+        features
+    }
+
+    fn action_features(&self, state: &TTTState, action: &TTTAction) -> Vec<f32> {
+        let mut features = vec![0.0; Self::ACTION_FEATURES];
+        features[3 * action.row + action.col] = 1.0;
+        let opponent = match state.next_player {
+            TTTPlayer::X => TTTPlayer::O,
+            TTTPlayer::O => TTTPlayer::X,
+        };
+        if Self::completes_line(state, action, &state.next_player) {
+            features[9] = 1.0;
+        }
+        if Self::completes_line(state, action, &opponent) {
+            features[10] = 1.0;
+        }
+        features
+    }
+}
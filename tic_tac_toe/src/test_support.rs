@@ -0,0 +1,39 @@
+//! Fixtures for the tests of the search crates
+
+use game_ai::{GameRules, GameStateTrait, Rewards};
+
+use crate::{GridCell, TTTPlayer, TTTRules, TTTState};
+
+impl TTTState {
+    /// X has the top left corner and its neighbor, O the middle of the left column and the
+    /// center. X is to move and wins by completing the top row.
+    pub fn winning_position() -> TTTState {
+        let mut state = TTTState::default();
+        for (row, col, player) in [
+            (0, 0, TTTPlayer::X),
+            (0, 1, TTTPlayer::X),
+            (1, 0, TTTPlayer::O),
+            (1, 1, TTTPlayer::O),
+        ] {
+            state.board[row][col] = GridCell::Occupied(player);
+        }
+        state
+    }
+
+    /// Rewards with perfect play of both players, found by searching the whole game tree
+    pub fn solve(&self) -> Rewards {
+        if self.is_final() {
+            return self.reward();
+        }
+        let player = self.next_player();
+        self.get_actions()
+            .iter()
+            .map(|action| TTTRules::play(self, action).solve())
+            .max_by(|value_1, value_2| {
+                value_1
+                    .for_player(&player)
+                    .total_cmp(&value_2.for_player(&player))
+            })
+            .unwrap()
+    }
+}